@@ -12,6 +12,7 @@ use galaxy_flow::model::task_report::task_rc_config::init_redirect_and_parent_ta
 use galaxy_flow::runner::{GxlCmd, GxlRunner};
 use galaxy_flow::traits::Setter;
 use galaxy_flow::util::redirect::stop_redirect;
+use galaxy_flow::util::signal::{install_signal_handler, is_interrupted, INTERRUPT_EXIT_CODE};
 use std::env;
 
 #[tokio::main]
@@ -23,6 +24,7 @@ async fn main() -> anyhow::Result<()> {
     // 检查是否请求版本信息
 
     let mut cmd = GxlCmd::parse();
+    install_signal_handler()?;
     // 加载task配置
 
     configure_run_logging(cmd.log.clone(), cmd.debug);
@@ -51,5 +53,8 @@ async fn main() -> anyhow::Result<()> {
         }
     }
     stop_redirect(redirect)?;
+    if is_interrupted() {
+        process::exit(INTERRUPT_EXIT_CODE);
+    }
     process::exit(-1);
 }
@@ -214,7 +214,16 @@ mod tests {
             quiet: Some(true),
             cmd_arg: String::new(),
             dryrun: false,
+            step: false,
+            plan: false,
+            resume: None,
+            matrix: None,
+            matrix_parallel: false,
+            record: None,
+            replay: None,
+            lock_timeout: None,
             mod_update: false,
+            action: None,
         })
         .await
         .assert();
@@ -23,6 +23,22 @@ pub fn take_string(data: &mut &str) -> Result<String> {
     .parse_next(data)
 }
 
+/// 单引号字符串, 用于写在注解参数(双引号)中的表达式; \' 转义单引号
+pub fn take_sq_string(data: &mut &str) -> Result<String> {
+    let string_parser = take_escaped(
+        take_while(1.., |c: char| c != '\'' && c != '\\'),
+        '\\',
+        one_of(['\'', '\\']),
+    );
+    delimited(
+        '\'',
+        string_parser.map(|x: &str| x.replace("\\'", "'").replace("\\\\", "\\")),
+        '\'',
+    )
+    .context(StrContext::Label("string"))
+    .parse_next(data)
+}
+
 pub fn take_number(data: &mut &str) -> Result<u64> {
     // 使用 take_escaped 解析转义字符
     let digit = digit1
@@ -127,6 +143,19 @@ mod tests {
         assert_eq!(take_string(&mut input), Ok("hello".to_string()));
     }
 
+    #[test]
+    fn test_take_sq_string() {
+        let mut input = "'x86_64*'";
+        assert_eq!(take_sq_string(&mut input), Ok("x86_64*".to_string()));
+        let mut input = r#"'it\'s "ok"' && x"#;
+        assert_eq!(take_sq_string(&mut input), Ok(r#"it's "ok""#.to_string()));
+        assert_eq!(input, " && x");
+        let mut input = "''";
+        assert_eq!(take_sq_string(&mut input), Ok("".to_string()));
+        let mut input = "'open";
+        assert!(take_sq_string(&mut input).is_err());
+    }
+
     #[test]
     fn test_gal_raw_string() {
         let mut input = "r#\"git branch --show-current |  sed -E \"s/(feature|develop|ver-dev|release|master|issue)(\\/.*)?/_branch_\\1/g\" \"#" ;
@@ -1,6 +1,6 @@
 use std::path::PathBuf;
 
-use crate::ability::prelude::*;
+use crate::ability::{plan_action, prelude::*};
 use getset::{Getters, Setters, WithSetters};
 use orion_error::ToStructError;
 use orion_variate::archive::{compress, decompress};
@@ -40,6 +40,14 @@ impl GxUnTar {
 impl AsyncRunnableTrait for GxTar {
     async fn async_exec(&self, ctx: ExecContext, vars_dict: VarSpace) -> TaskResult {
         let ex = EnvExpress::from_env_mix(vars_dict.global().clone());
+        if *ctx.plan() {
+            let detail = format!(
+                "{} ---> {}",
+                ex.sec_eval(&self.src)?,
+                ex.sec_eval(&self.file)?
+            );
+            return plan_action("gx.tar", detail, vars_dict);
+        }
         let src = PathBuf::from(ex.eval(&self.src)?);
         let dst = PathBuf::from(ex.eval(&self.file)?);
         info!(target: ctx.path(), "archive {}  -> {}", src.display(), dst.display());
@@ -67,6 +75,14 @@ impl ComponentMeta for GxTar {
 impl AsyncRunnableTrait for GxUnTar {
     async fn async_exec(&self, ctx: ExecContext, vars_dict: VarSpace) -> TaskResult {
         let ex = EnvExpress::from_env_mix(vars_dict.global().clone());
+        if *ctx.plan() {
+            let detail = format!(
+                "{} ---> {}",
+                ex.sec_eval(&self.file)?,
+                ex.sec_eval(&self.dst)?
+            );
+            return plan_action("gx.untar", detail, vars_dict);
+        }
         let src = PathBuf::from(ex.eval(&self.file)?);
         let out = PathBuf::from(ex.eval(&self.dst)?);
         info!(target: ctx.path(), "untar {}  -> {}", src.display(), out.display());
@@ -1,4 +1,9 @@
-use crate::{ability::prelude::*, expect::LogicScope};
+use crate::{
+    ability::{plan_action, prelude::*},
+    execution::retry::RetryPolicy,
+    expect::LogicScope,
+    util::shell::run_blocking,
+};
 
 #[derive(Clone, Debug, Default, Builder, PartialEq, Getters)]
 pub struct GxCmd {
@@ -8,6 +13,8 @@ pub struct GxCmd {
 pub struct GxCmdDto {
     pub cmd: String,
     pub expect: ShellOption,
+    #[builder(default)]
+    pub retry: Option<RetryPolicy>,
 }
 impl GxCmdDto {
     pub fn update(&mut self, vars_dict: &VarSpace) -> ExecResult<()> {
@@ -19,7 +26,20 @@ impl GxCmdDto {
 #[async_trait]
 impl AsyncRunnableTrait for GxCmd {
     async fn async_exec(&self, ctx: ExecContext, vars_dict: VarSpace) -> TaskResult {
-        self.execute_impl(&self.dto.cmd, ctx, vars_dict)
+        match &self.dto.retry {
+            Some(policy) => {
+                policy
+                    .retry_exec("gx.cmd", || {
+                        let (this, ctx, vars_dict) = (self.clone(), ctx.clone(), vars_dict.clone());
+                        run_blocking(move || this.execute_impl(&this.dto.cmd, ctx, vars_dict))
+                    })
+                    .await
+            }
+            None => {
+                let this = self.clone();
+                run_blocking(move || this.execute_impl(&this.dto.cmd, ctx, vars_dict)).await
+            }
+        }
     }
 }
 impl ComponentMeta for GxCmd {
@@ -44,6 +64,9 @@ impl GxCmd {
         let mut action = Action::from("gx.cmd");
         trace!(target:ctx.path(),"cmd:{cmd}", );
         let exp = EnvExpress::from_env_mix(vars_dict.global().clone());
+        if *ctx.plan() {
+            return plan_action("gx.cmd", exp.sec_eval(cmd)?, vars_dict);
+        }
         let exe_cmd = exp.eval(cmd)?;
 
         let mut expect = self.dto.expect.clone();
@@ -51,14 +74,16 @@ impl GxCmd {
         if let Some(quiet) = ctx.quiet() {
             expect.quiet = quiet;
         }
+        expect.timeout = ctx.cmd_timeout(expect.timeout);
+        expect.prefix = Some(ctx.path().to_string());
 
-        let res = gxl_sh!(
+        let res = ctx.executor().run(
             LogicScope::Outer,
             ctx.tag_path("cmd").as_str(),
             &exe_cmd,
             &expect,
             &exp,
-            vars_dict.global()
+            vars_dict.global(),
         );
         match res {
             Ok((stdout, stderr)) => {
@@ -84,6 +109,7 @@ impl GxCmd {
 #[cfg(test)]
 mod tests {
     use orion_error::TestAssertWithMsg;
+    use orion_variate::vars::ValueType;
 
     use super::*;
     use crate::{ability::*, traits::Setter};
@@ -99,6 +125,24 @@ mod tests {
         let _ = res.async_exec(context, def).await.assert("dryrun");
     }
 
+    #[tokio::test]
+    async fn cmd_plan_test() {
+        let (context, mut def) = ability_env_init();
+        def.global_mut()
+            .sec_set("TOKEN", ValueType::from("galaxy".to_string()));
+        let res = GxCmd::new("echo ${TOKEN} > ./.plan_out; exit 1".into());
+        let TaskValue { rec, .. } = res
+            .async_exec(context.with_plan(true), def)
+            .await
+            .assert("plan");
+        let ExecOut::Action(action) = rec else {
+            panic!("need action");
+        };
+        assert!(action.stdout.starts_with("echo "));
+        assert!(!action.stdout.contains("galaxy"));
+        assert!(!std::path::Path::new("./.plan_out").exists());
+    }
+
     #[tokio::test]
     async fn cmd_test_err() {
         let (context, mut def) = ability_env_init();
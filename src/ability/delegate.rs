@@ -8,11 +8,11 @@ use crate::ability::prelude::*;
 use crate::components::gxl_act::activity::Activity;
 use crate::components::gxl_fun::fun::GxlFun;
 use crate::components::gxl_spc::GxlSpace;
-use crate::execution::runnable::AsyncRunnableArgsTrait;
+use crate::execution::runnable::{AsyncRunnableArgsTrait, BlockCtrl};
 use crate::model::components::gxl_utls::mod_obj_name;
 
 use crate::primitive::{GxlAParam, GxlAParams, GxlFParams};
-use crate::traits::DependTrait;
+use crate::traits::{DependTrait, Setter};
 
 #[derive(Clone, From)]
 pub enum ActTypes {
@@ -28,6 +28,9 @@ pub struct ActCall {
     #[getset(set_with = "pub")]
     assembled: bool,
     act: Option<ActTypes>,
+    // VER = ver.current(); 接收返回值的变量
+    #[getset(set_with = "pub")]
+    ret_name: Option<String>,
 }
 
 impl From<String> for ActCall {
@@ -38,6 +41,7 @@ impl From<String> for ActCall {
             actual_params: GxlAParams::new(),
             act: None,
             assembled: false,
+            ret_name: None,
         }
     }
 }
@@ -53,6 +57,7 @@ impl From<(String, Vec<GxlAParam>)> for ActCall {
             actual_params: args,
             assembled: false,
             act: None,
+            ret_name: None,
         }
     }
 }
@@ -139,7 +144,26 @@ impl AsyncRunnableTrait for ActCall {
     async fn async_exec(&self, mut ctx: ExecContext, vars_dict: VarSpace) -> TaskResult {
         ctx.append("@");
         match &self.act {
-            Some(act) => act.async_exec(ctx, vars_dict, &self.actual_params).await,
+            Some(act) => {
+                let TaskValue {
+                    mut vars,
+                    rec,
+                    ctrl,
+                } = act.async_exec(ctx, vars_dict, &self.actual_params).await?;
+                // return 止于调用处, 不再向外传递
+                if let Some(name) = &self.ret_name {
+                    match ctrl {
+                        Some(BlockCtrl::Return(Some(value))) => {
+                            vars.global_mut().set(name.clone(), value)
+                        }
+                        _ => {
+                            return ExecReason::NoVal(format!("{} return for {name}", self.name))
+                                .err_result()
+                        }
+                    }
+                }
+                Ok(TaskValue::from((vars, rec)))
+            }
             None => Err(ExecError::from(ExecReason::Depend(format!(
                 "act call not support :{}",
                 self.name
@@ -8,6 +8,7 @@ use crate::const_val::gxl_const;
 use crate::execution::runnable::AsyncRunnableWithSenderTrait;
 use crate::sec::SecValueType;
 use crate::util::redirect::ReadSignal;
+use crate::util::step;
 use crate::{
     runner::{GxlCmd, GxlRunner},
     util::path::WorkDir,
@@ -74,7 +75,16 @@ impl AsyncRunnableWithSenderTrait for GxRun {
             quiet: ctx.quiet(),
             cmd_arg: String::new(),
             dryrun,
+            plan: *ctx.plan(),
+            step: step::is_enabled(),
+            resume: None,
+            matrix: None,
+            matrix_parallel: false,
+            lock_timeout: None,
+            record: None,
+            replay: None,
             mod_update,
+            action: None,
         };
         let run_path = exp.eval(&self.run_path)?;
         let _g = WorkDir::change(run_path)
@@ -82,7 +92,7 @@ impl AsyncRunnableWithSenderTrait for GxRun {
             .with(self.run_path().clone())?;
         debug!(target:ctx.path(), "{:#?}", cmd);
         let sub_var_space = VarSpace::inherit_init(vars_dict.clone(), self.env_isolate)?;
-        GxlRunner::run(cmd, sub_var_space, sender)
+        GxlRunner::run_with_executor(cmd, sub_var_space, sender, ctx.executor().clone())
             .await
             .err_conv()?;
         action.finish();
@@ -10,7 +10,11 @@ use orion_variate::{
     update::{DownloadOptions, HttpMethod, UploadOptions},
 };
 
-use crate::{ability::prelude::*, util::accessor::build_accessor};
+use crate::{
+    ability::{plan_action, prelude::*},
+    execution::retry::RetryPolicy,
+    util::accessor::build_accessor,
+};
 
 #[derive(Clone, Default, Debug, PartialEq, Builder, Getters)]
 #[builder(setter(into))]
@@ -22,6 +26,8 @@ pub struct GxUpLoad {
     #[builder(default)]
     password: Option<String>,
     method: String,
+    #[builder(default)]
+    retry: Option<RetryPolicy>,
 }
 
 #[derive(Clone, Default, Debug, PartialEq, Builder, Getters)]
@@ -33,11 +39,35 @@ pub struct GxDownLoad {
     username: Option<String>,
     #[builder(default)]
     password: Option<String>,
+    #[builder(default)]
+    retry: Option<RetryPolicy>,
 }
 
 #[async_trait]
 impl AsyncRunnableTrait for GxUpLoad {
-    async fn async_exec(&self, _ctx: ExecContext, vars_dict: VarSpace) -> TaskResult {
+    async fn async_exec(&self, ctx: ExecContext, vars_dict: VarSpace) -> TaskResult {
+        if *ctx.plan() {
+            let ex = EnvExpress::from_env_mix(vars_dict.global().clone());
+            let detail = format!(
+                "{} ---> {}",
+                ex.sec_eval(self.local_file())?,
+                ex.sec_eval(self.svc_url())?
+            );
+            return plan_action("gx.upload", detail, vars_dict);
+        }
+        match self.retry() {
+            Some(policy) => {
+                policy
+                    .retry_exec("gx.upload", || self.upload(vars_dict.clone()))
+                    .await
+            }
+            None => self.upload(vars_dict).await,
+        }
+    }
+}
+
+impl GxUpLoad {
+    async fn upload(&self, vars_dict: VarSpace) -> TaskResult {
         let ex = EnvExpress::from_env_mix(vars_dict.global().clone());
         let mut addr = HttpResource::from(ex.eval(self.svc_url())?);
         if let (Some(username), Some(password)) = (self.username(), self.password()) {
@@ -83,6 +113,28 @@ impl ComponentMeta for GxUpLoad {
 impl AsyncRunnableTrait for GxDownLoad {
     async fn async_exec(&self, mut ctx: ExecContext, vars_dict: VarSpace) -> TaskResult {
         ctx.append("gx.download");
+        if *ctx.plan() {
+            let ex = EnvExpress::from_env_mix(vars_dict.global().clone());
+            let detail = format!(
+                "{} ---> {}",
+                ex.sec_eval(self.remote_url())?,
+                ex.sec_eval(self.local_file())?
+            );
+            return plan_action("gx.download", detail, vars_dict);
+        }
+        match self.retry() {
+            Some(policy) => {
+                policy
+                    .retry_exec("gx.download", || self.download(&ctx, vars_dict.clone()))
+                    .await
+            }
+            None => self.download(&ctx, vars_dict).await,
+        }
+    }
+}
+
+impl GxDownLoad {
+    async fn download(&self, ctx: &ExecContext, vars_dict: VarSpace) -> TaskResult {
         let ex = EnvExpress::from_env_mix(vars_dict.global().clone());
         let mut addr = HttpResource::from(ex.eval(self.remote_url())?);
         if let (Some(username), Some(password)) = (self.username(), self.password()) {
@@ -119,9 +171,7 @@ impl AsyncRunnableTrait for GxDownLoad {
                 .with(&local_file_path);
         }
     }
-}
 
-impl GxDownLoad {
     fn get_final_path(&self, local_file_path: &Path, addr: &HttpResource) -> PathBuf {
         if local_file_path.is_dir() {
             // 如果传入的是目录，从URL中提取文件名
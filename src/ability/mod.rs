@@ -11,7 +11,7 @@ pub mod tpl;
 //pub mod vault;
 pub mod shell;
 pub mod version;
-use prelude::VarSpace;
+use prelude::{Action, ExecOut, TaskResult, TaskValue, VarSpace};
 
 use crate::const_val::gxl_const;
 use crate::{context::ExecContext, infra::once_init_log, traits::Setter, ExecResult};
@@ -36,6 +36,15 @@ pub fn sudo_cmd(sudo: bool) -> String {
     }
 }
 
+/// --plan 模式下代替实际执行: 打印解析后的参数(敏感变量保持掩码), 不产生副作用
+pub fn plan_action(name: &str, detail: String, vars: VarSpace) -> TaskResult {
+    println!("[plan] {name:<12} {detail}");
+    let mut action = Action::from(name);
+    action.stdout = detail;
+    action.finish();
+    Ok(TaskValue::from((vars, ExecOut::Action(action))))
+}
+
 pub fn parse_suc_code(suc: &str) -> Vec<i32> {
     let expect: Vec<&str> = suc.split(',').collect();
     let mut expect_vec = Vec::new();
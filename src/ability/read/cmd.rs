@@ -1,4 +1,4 @@
-use crate::ability::prelude::*;
+use crate::ability::{plan_action, prelude::*};
 use crate::components::GxlProps;
 use crate::expect::{LogicScope, ShellOption};
 
@@ -17,14 +17,22 @@ impl CmdDTO {
         let exp = EnvExpress::from_env_mix(vars_dict.global().clone());
         let cmd = self.cmd.clone();
         let name = self.name.clone();
+        // --plan 下同样不执行读取命令, 变量以占位值代替, 便于后续命令继续解析
+        if *ctx.plan() {
+            vars_dict.global_mut().set(&name, format!("<{name}>"));
+            return plan_action("gx.read_cmd", exp.sec_eval(&cmd)?, vars_dict);
+        }
         let cmd = exp.eval(&cmd)?;
-        let (data, _) = gxl_sh!(
+        let mut expect = self.expect.clone();
+        expect.timeout = ctx.cmd_timeout(expect.timeout);
+        expect.prefix = Some(ctx.path().to_string());
+        let (data, _) = ctx.executor().run(
             LogicScope::Outer,
             ctx.path(),
             &cmd,
-            &self.expect,
+            &expect,
             &exp,
-            vars_dict.global()
+            vars_dict.global(),
         )?;
         let data_str =
             String::from_utf8(data).map_err(|msg| ExecReason::Exp(format!("bad result {msg}")))?;
@@ -54,4 +62,20 @@ mod tests {
         let res = GxRead::from(ReadMode::from(dto));
         res.async_exec(context, def).await.unwrap();
     }
+
+    #[tokio::test]
+    async fn read_cmd_plan_test() {
+        let (context, def) = ability_env_init();
+        let dto = CmdDTO {
+            name: "RG".to_string(),
+            cmd: "touch ./.plan_read; echo galaxy-1.0".to_string(),
+            ..Default::default()
+        };
+        let res = GxRead::from(ReadMode::from(dto));
+        let TaskValue { vars, rec, .. } =
+            res.async_exec(context.with_plan(true), def).await.unwrap();
+        assert!(matches!(rec, ExecOut::Action(_)));
+        assert_eq!(vars.get("RG").unwrap().to_string(), "<RG>");
+        assert!(!std::path::Path::new("./.plan_read").exists());
+    }
 }
@@ -5,6 +5,7 @@ use crate::{
     ability::prelude::{AsyncRunnableTrait, ComponentMeta, TaskResult, VarSpace},
     context::ExecContext,
     meta::GxlMeta,
+    util::shell::run_blocking,
     ExecReason,
 };
 
@@ -26,7 +27,14 @@ pub enum ReadMode {
 #[async_trait]
 impl AsyncRunnableTrait for GxRead {
     async fn async_exec(&self, ctx: ExecContext, vars_dict: VarSpace) -> TaskResult {
-        self.execute_impl(ctx, vars_dict)
+        match &self.imp {
+            // 命令与标准输入会阻塞线程
+            ReadMode::CMD(_) | ReadMode::STDIN(_) => {
+                let this = self.clone();
+                run_blocking(move || this.execute_impl(ctx, vars_dict)).await
+            }
+            _ => self.execute_impl(ctx, vars_dict),
+        }
     }
 }
 
@@ -3,7 +3,13 @@ use orion_common::serde::*;
 use rand::Rng;
 use std::path::PathBuf;
 
-use crate::{ability::prelude::*, expect::LogicScope, traits::Setter, var::VarDict};
+use crate::{
+    ability::{plan_action, prelude::*},
+    expect::LogicScope,
+    traits::Setter,
+    util::shell::run_blocking,
+    var::VarDict,
+};
 use getset::{Getters, MutGetters, Setters, WithSetters};
 use orion_error::UvsLogicFrom;
 use orion_variate::vars::ValueDict;
@@ -21,7 +27,8 @@ pub struct GxShell {
 #[async_trait]
 impl AsyncRunnableTrait for GxShell {
     async fn async_exec(&self, ctx: ExecContext, vars_dict: VarSpace) -> TaskResult {
-        self.execute_impl(ctx, vars_dict)
+        let this = self.clone();
+        run_blocking(move || this.execute_impl(ctx, vars_dict)).await
     }
 }
 impl ComponentMeta for GxShell {
@@ -42,6 +49,14 @@ impl GxShell {
         let mut action = Action::from("gx.shell");
         trace!(target:ctx.path(),"shell:{}", self.shell);
         let exp = EnvExpress::from_env_mix(vars_dict.global().clone());
+        if *ctx.plan() {
+            let detail = exp.sec_eval(self.shell.as_str())?;
+            // 输出变量以占位值代替, 便于后续命令继续解析
+            if let Some(out_var) = &self.out_var {
+                vars_dict.global_mut().set(out_var, format!("<{out_var}>"));
+            }
+            return plan_action("gx.shell", detail, vars_dict);
+        }
         let ext_cmd = exp.eval(self.shell.as_str())?;
         let mut expect = self.expect.clone();
 
@@ -49,6 +64,8 @@ impl GxShell {
         if let Some(quiet) = ctx.quiet() {
             expect.quiet = quiet;
         }
+        expect.timeout = ctx.cmd_timeout(expect.timeout);
+        expect.prefix = Some(ctx.path().to_string());
         if let Some(arg_file) = &self.arg_file {
             let dict = if arg_file.extension() == PathBuf::from("data.json").extension() {
                 ValueDict::from_json(arg_file).owe_data()?
@@ -88,13 +105,13 @@ impl GxShell {
                 .global_mut()
                 .set(out_var, format!("{}", out_data_path.display()));
             //let exe_cmd = format!("{ext_cmd}");
-            let res = gxl_sh!(
+            let res = ctx.executor().run(
                 LogicScope::Outer,
                 ctx.tag_path("cmd").as_str(),
                 &ext_cmd,
                 &expect,
                 &exp,
-                vars_dict.global()
+                vars_dict.global(),
             );
             let file_out = std::fs::read_to_string(&out_data_path)
                 .map_err(|e| ExecReason::Io(e.to_string()))?;
@@ -104,13 +121,13 @@ impl GxShell {
             std::fs::remove_file(out_data_path).owe_logic()?;
             res
         } else {
-            gxl_sh!(
+            ctx.executor().run(
                 LogicScope::Outer,
                 ctx.tag_path("cmd").as_str(),
                 &ext_cmd,
                 &expect,
                 &exp,
-                vars_dict.global()
+                vars_dict.global(),
             )
         };
 
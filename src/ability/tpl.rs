@@ -1,4 +1,4 @@
-use crate::ability::prelude::*;
+use crate::ability::{plan_action, prelude::*};
 use crate::execution::action::Action;
 use handlebars::{to_json, Handlebars};
 use orion_error::WithContext;
@@ -223,6 +223,15 @@ impl GxTpl {
 #[async_trait]
 impl AsyncRunnableTrait for GxTpl {
     async fn async_exec(&self, ctx: ExecContext, vars_dict: VarSpace) -> TaskResult {
+        if *ctx.plan() {
+            let exp = EnvExpress::from_env_mix(vars_dict.global().clone());
+            let detail = format!(
+                "{} ---> {}",
+                exp.sec_eval(&self.dto.tpl)?,
+                exp.sec_eval(&self.dto.dst)?
+            );
+            return plan_action("gx.tpl", detail, vars_dict);
+        }
         let mut action = Action::from("build tpl file");
         self.render_path(ctx, &self.dto, vars_dict.clone())?;
         action.finish();
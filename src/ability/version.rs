@@ -1,4 +1,4 @@
-use crate::ability::prelude::*;
+use crate::ability::{plan_action, prelude::*};
 
 use crate::execution::runnable::ComponentMeta;
 use crate::parser::domain::take_version;
@@ -124,6 +124,9 @@ impl AsyncRunnableTrait for GxlVersion {
             ver.auto(&self.verinc);
             dict.global_mut()
                 .set(&self.export.to_uppercase(), format!("{}", &ver));
+            if *ctx.plan() {
+                return plan_action("gx.ver", format!("{file_path} ---> {ver}"), dict);
+            }
             let mut file = File::create(file_path.as_str()).owe_res()?;
             file.write_all(ver.to_string().as_bytes()).owe_res()?;
             Ok(TaskValue::from((dict, ExecOut::Ignore)))
@@ -14,6 +14,12 @@ pub mod gxl_const {
     pub const START_ROOT: &str = "GXL_START_ROOT";
     /// 当前目录变量
     pub const CUR_DIR: &str = "GXL_CUR_DIR";
+    /// 失败的 flow, 供 #[on_failure] / #[always] 使用
+    pub const FAIL_FLOW: &str = "GXL_FAIL_FLOW";
+    /// 失败的错误信息
+    pub const FAIL_ERR: &str = "GXL_FAIL_ERR";
+    /// 执行结果: suc / fail
+    pub const RUN_STATUS: &str = "GXL_RUN_STATUS";
     /// 错误消息前缀
     pub const ERROR_PREFIX: &str = "GXL ERROR: ";
     pub const CONFIG_FILE: &str = "conf.toml";
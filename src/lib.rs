@@ -47,6 +47,7 @@ pub mod util;
 
 pub mod galaxy;
 pub mod infra;
+pub mod repl;
 pub mod runner;
 pub mod types;
 pub use crate::loader::{get_parse_code, GxLoader};
@@ -1,6 +1,8 @@
 use std::collections::HashMap;
 
 use super::components::gxl_flow::meta::FlowMetaHold;
+use super::execution::trans::UndoFailPolicy;
+use crate::ExecResult;
 
 #[derive(Clone, Default, Debug, PartialEq)]
 pub struct Annotation<T> {
@@ -40,8 +42,13 @@ pub trait ComUsage {
 pub trait Transaction {
     fn is_transaction(&self) -> bool;
     fn undo_hold(&self) -> Option<FlowMetaHold>;
+    fn undo_fail_policy(&self) -> ExecResult<UndoFailPolicy>;
 }
 
 pub trait Dryrunable {
     fn dryrun_hold(&self) -> Option<FlowMetaHold>;
 }
+
+pub trait Parallelable {
+    fn is_parallel(&self) -> bool;
+}
@@ -1,10 +1,14 @@
 use crate::{
-    ability::prelude::{Action, TaskValue},
+    ability::{
+        plan_action,
+        prelude::{Action, TaskValue},
+    },
     evaluator::VarParser,
     execution::runnable::AsyncRunnableArgsTrait,
     meta::MetaInfo,
     model::components::prelude::*,
-    primitive::GxlAParams,
+    primitive::{GxlAParams, GxlObject},
+    sec::SecValueType,
 };
 use async_trait::async_trait;
 use orion_common::friendly::AppendAble;
@@ -19,6 +23,7 @@ use crate::{
     meta::GxlMeta,
     traits::DependTrait,
     types::Property,
+    util::shell::run_blocking,
 };
 
 use super::meta::ActivityMeta;
@@ -53,7 +58,8 @@ impl ActivityDTO {
 #[async_trait]
 impl AsyncRunnableArgsTrait for Activity {
     async fn async_exec(&self, ctx: ExecContext, vars: VarSpace, args: &GxlAParams) -> TaskResult {
-        self.exec_cmd(ctx, vars, args)
+        let (this, args) = (self.clone(), args.clone());
+        run_blocking(move || this.exec_cmd(ctx, vars, &args)).await
     }
 }
 impl ComponentMeta for Activity {
@@ -83,9 +89,12 @@ impl Activity {
 
         let mut r_with = WithContext::want("run shell");
         let exp = EnvExpress::from_env_mix(dict.global().clone());
-        let cmd = exp
-            .eval(dict.must_get("executer")?.to_string().as_str())
-            .with(&r_with)?;
+        let executer = dict.must_get("executer")?.to_string();
+        if *ctx.plan() {
+            let detail = exp.sec_eval(executer.as_str()).with(&r_with)?;
+            return plan_action(self.meta().full_name().as_str(), detail, vars_dict);
+        }
+        let cmd = exp.eval(executer.as_str()).with(&r_with)?;
         r_with.with("exec", cmd.clone());
 
         //let mut opt = dict.get("expect").clone();
@@ -94,19 +103,40 @@ impl Activity {
         if let Some(quiet) = ctx.quiet() {
             opt.quiet = quiet;
         }
-
-        gxl_sh!(
-            LogicScope::Outer,
-            ctx.path(),
-            &cmd,
-            &opt,
-            &exp,
-            dict.global()
-        )
-        .with(&r_with)?;
+        opt.timeout = ctx.cmd_timeout(opt.timeout);
+        opt.prefix = Some(ctx.path().to_string());
+        Self::shell_setting(&dict, args, &mut opt);
+
+        ctx.executor()
+            .run(
+                LogicScope::Outer,
+                ctx.path(),
+                &cmd,
+                &opt,
+                &exp,
+                dict.global(),
+            )
+            .with(&r_with)?;
         action.finish();
         Ok(TaskValue::from((vars_dict, ExecOut::Action(action))))
     }
+    // dir/env/stdin 为执行参数, 无需在 activity 中声明
+    fn shell_setting(dict: &VarSpace, args: &GxlAParams, opt: &mut ShellOption) {
+        let arg_value = |name: &str| {
+            args.get(name).and_then(|x| match x.value() {
+                GxlObject::VarRef(var) => dict.get(var.as_str()),
+                GxlObject::Value(value) => Some(value.clone()),
+            })
+        };
+        opt.dir = arg_value("dir").map(|x| x.to_string());
+        opt.stdin = arg_value("stdin").map(|x| x.to_string());
+        if let Some(SecValueType::Obj(env)) = arg_value("env") {
+            opt.env = env
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+        }
+    }
     pub fn exec_cmd(&self, ctx: ExecContext, vars_dict: VarSpace, args: &GxlAParams) -> TaskResult {
         self.execute_impl(ctx, vars_dict, args)
     }
@@ -166,4 +196,24 @@ mod tests {
 
         activity.exec_cmd(ctx, vars, &args).assert();
     }
+
+    #[test]
+    fn test_exec_cmd_plan() {
+        ability_env_init();
+        let meta =
+            ActivityMeta::build("test_activity")
+                .with_params(vec![GxlFParam::new("executer").with_default_value(
+                    SecValueType::nor_from("touch ./.plan_act; exit 1".to_string()).to_opt(),
+                )]);
+        let activity = Activity::new(meta);
+        let ctx = ExecContext::new(Some(false), false).with_plan(true);
+        let TaskValue { rec, .. } = activity
+            .exec_cmd(ctx, VarSpace::default(), &GxlAParams::new())
+            .assert();
+        let ExecOut::Action(action) = rec else {
+            panic!("need action");
+        };
+        assert_eq!(action.stdout, "touch ./.plan_act; exit 1");
+        assert!(!std::path::Path::new("./.plan_act").exists());
+    }
 }
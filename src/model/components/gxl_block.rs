@@ -1,10 +1,12 @@
 use super::gxl_cond::GxlCond;
 use super::gxl_loop::GxlLoop;
 use super::gxl_spc::GxlSpace;
+use super::gxl_try::GxlTry;
 use super::gxl_var::GxlVar;
 use super::prelude::*;
 use async_trait::async_trait;
 use derive_more::From;
+use orion_error::ToStructError;
 use std::sync::mpsc::Sender;
 
 use crate::ability::archive::GxTar;
@@ -17,9 +19,12 @@ use crate::ability::{
 };
 use crate::calculate::cond::CondExec;
 use crate::context::ExecContext;
-use crate::execution::runnable::{AsyncRunnableWithSenderTrait, TaskResult};
+use crate::execution::runnable::{AsyncRunnableWithSenderTrait, BlockCtrl, TaskResult};
 use crate::execution::task::Task;
+use crate::primitive::GxlObject;
+use crate::sec::{SecFrom, SecString, SecValueType};
 use crate::util::redirect::ReadSignal;
+use crate::util::step::{self, StepDecision};
 
 #[derive(Clone, From)]
 pub enum BlockAction {
@@ -28,6 +33,7 @@ pub enum BlockAction {
     GxlRun(GxRun),
     Cond(GxlCond),
     Loop(GxlLoop),
+    Try(GxlTry),
     Echo(GxEcho),
     Assert(GxAssert),
     Version(GxlVersion),
@@ -38,6 +44,8 @@ pub enum BlockAction {
     UnTar(GxUnTar),
     DownLoad(GxDownLoad),
     UpLoad(GxUpLoad),
+    Ctrl(BlockCtrl),
+    Return(Option<GxlObject>),
 }
 
 #[derive(Clone, Getters, Default)]
@@ -72,6 +80,7 @@ impl AsyncRunnableWithSenderTrait for BlockAction {
         match self {
             BlockAction::GxlRun(o) => o.async_exec(ctx, dct, sender).await,
             BlockAction::Loop(o) => o.async_exec(ctx, dct, sender).await,
+            BlockAction::Try(o) => o.async_exec(ctx, dct, sender).await,
             BlockAction::Shell(o) => o.async_exec(ctx, dct).await,
             BlockAction::Command(o) => o.async_exec(ctx, dct).await,
             BlockAction::Echo(o) => o.async_exec(ctx, dct).await,
@@ -85,6 +94,17 @@ impl AsyncRunnableWithSenderTrait for BlockAction {
             BlockAction::Read(o) => o.async_exec(ctx, dct).await,
             BlockAction::UpLoad(o) => o.async_exec(ctx, dct).await,
             BlockAction::DownLoad(o) => o.async_exec(ctx, dct).await,
+            BlockAction::Ctrl(o) => {
+                Ok(TaskValue::from((dct, ExecOut::Ignore)).with_ctrl(Some(o.clone())))
+            }
+            BlockAction::Return(o) => {
+                let value = match o {
+                    Some(obj) => Some(return_value(obj, &dct)?),
+                    None => None,
+                };
+                Ok(TaskValue::from((dct, ExecOut::Ignore))
+                    .with_ctrl(Some(BlockCtrl::Return(value))))
+            }
         }
     }
 }
@@ -102,18 +122,80 @@ impl AsyncRunnableWithSenderTrait for BlockNode {
         let mut cur_var_dict = var_dict;
         self.export_props(ctx.clone(), cur_var_dict.global_mut(), "")?;
 
+        let mut block_ctrl = None;
         for item in &self.items {
-            let TaskValue { vars, rec } = item
+            if step::is_enabled() {
+                let detail = item.step_detail(&cur_var_dict);
+                if step::pause(ctx.path(), &detail, &mut cur_var_dict)? == StepDecision::Skip {
+                    continue;
+                }
+            }
+            let TaskValue { vars, rec, ctrl } = item
                 .async_exec(ctx.clone(), cur_var_dict, sender.clone())
                 .await?;
             cur_var_dict = vars;
             // task.stdout.push_str(out.as_str());
             task.append(rec);
+            // break/continue/return 跳过块内剩余语句
+            if ctrl.is_some() {
+                block_ctrl = ctrl;
+                break;
+            }
         }
         task.finish();
-        Ok(TaskValue::from((cur_var_dict, ExecOut::Task(task))))
+        Ok(TaskValue::from((cur_var_dict, ExecOut::Task(task))).with_ctrl(block_ctrl))
     }
 }
+impl BlockAction {
+    // --step 暂停时展示的内容; 命令先求值, 敏感变量保持掩码
+    fn step_detail(&self, dict: &VarSpace) -> String {
+        let exp = EnvExpress::from_env_mix(dict.global().clone());
+        let eval = |text: &str| exp.sec_eval(text).unwrap_or_else(|_| text.to_string());
+        match self {
+            BlockAction::Command(o) => format!("gx.cmd: {}", eval(o.dto().cmd.as_str())),
+            BlockAction::Shell(o) => format!("gx.shell: {}", eval(o.shell().as_str())),
+            BlockAction::GxlRun(o) => o.gxl_meta().name().to_string(),
+            BlockAction::Cond(_) => "if".to_string(),
+            BlockAction::Loop(_) => "for".to_string(),
+            BlockAction::Try(_) => "try".to_string(),
+            BlockAction::Echo(o) => o.gxl_meta().name().to_string(),
+            BlockAction::Assert(o) => o.gxl_meta().name().to_string(),
+            BlockAction::Version(o) => o.gxl_meta().name().to_string(),
+            BlockAction::Read(o) => o.gxl_meta().name().to_string(),
+            BlockAction::Call(o) => format!("call {}", o.name),
+            BlockAction::Tpl(o) => o.gxl_meta().name().to_string(),
+            BlockAction::Tar(o) => o.gxl_meta().name().to_string(),
+            BlockAction::UnTar(o) => o.gxl_meta().name().to_string(),
+            BlockAction::DownLoad(o) => o.gxl_meta().name().to_string(),
+            BlockAction::UpLoad(o) => o.gxl_meta().name().to_string(),
+            BlockAction::Ctrl(BlockCtrl::Break) => "break".to_string(),
+            BlockAction::Ctrl(BlockCtrl::Continue) => "continue".to_string(),
+            BlockAction::Ctrl(BlockCtrl::Return(_)) | BlockAction::Return(_) => {
+                "return".to_string()
+            }
+        }
+    }
+}
+
+// return 的值: 变量引用取当前值, 字符串先求值
+fn return_value(obj: &GxlObject, dict: &VarSpace) -> ExecResult<SecValueType> {
+    match obj {
+        GxlObject::VarRef(name) => dict
+            .get(name.as_str())
+            .ok_or(ExecReason::Miss(name.clone()).to_err()),
+        GxlObject::Value(SecValueType::String(v)) => {
+            let exp = EnvExpress::from_env_mix(dict.global().clone());
+            let value = exp.eval(v.value())?;
+            Ok(if v.is_secret() {
+                SecValueType::String(SecString::sec_from(value))
+            } else {
+                SecValueType::nor_from(value)
+            })
+        }
+        GxlObject::Value(value) => Ok(value.clone()),
+    }
+}
+
 impl DependTrait<&GxlSpace> for BlockNode {
     fn assemble(self, mod_name: &str, src: &GxlSpace) -> AResult<Self> {
         //let mut ins = BlockNode::default();
@@ -129,6 +211,7 @@ impl DependTrait<&GxlSpace> for BlockNode {
                 BlockAction::UnTar(v) => BlockAction::UnTar(v.clone()),
                 BlockAction::Cond(v) => BlockAction::Cond(v.clone()),
                 BlockAction::Loop(v) => BlockAction::Loop(v.clone()),
+                BlockAction::Try(v) => BlockAction::Try(v.clone()),
                 BlockAction::Read(v) => BlockAction::Read(v.clone()),
                 BlockAction::Echo(v) => BlockAction::Echo(v.clone()),
                 //BlockAction::Vault(v) => BlockAction::Vault(v.clone()),
@@ -140,6 +223,8 @@ impl DependTrait<&GxlSpace> for BlockNode {
                 BlockAction::Call(v) => BlockAction::Call(Box::new(v.assemble(mod_name, src)?)),
                 BlockAction::DownLoad(v) => BlockAction::DownLoad(v.clone()),
                 BlockAction::UpLoad(v) => BlockAction::UpLoad(v.clone()),
+                BlockAction::Ctrl(v) => BlockAction::Ctrl(v),
+                BlockAction::Return(v) => BlockAction::Return(v),
             };
             ins.append(item);
         }
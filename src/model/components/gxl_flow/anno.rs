@@ -16,6 +16,17 @@ pub enum FlowAnnFunc {
     Dryrun,
     Transaction,
     Undo,
+    Parallel,
+    Timeout,
+    Retry,
+    When,
+    OnFailure,
+    Always,
+    Inputs,
+    Outputs,
+    Lock,
+    Test,
+    ShouldFail,
 }
 
 impl From<&str> for FlowAnnFunc {
@@ -27,6 +38,17 @@ impl From<&str> for FlowAnnFunc {
             "dryrun" => FlowAnnFunc::Dryrun,
             "transaction" => FlowAnnFunc::Transaction,
             "undo" => FlowAnnFunc::Undo,
+            "parallel" => FlowAnnFunc::Parallel,
+            "timeout" => FlowAnnFunc::Timeout,
+            "retry" => FlowAnnFunc::Retry,
+            "when" => FlowAnnFunc::When,
+            "on_failure" => FlowAnnFunc::OnFailure,
+            "always" => FlowAnnFunc::Always,
+            "inputs" => FlowAnnFunc::Inputs,
+            "outputs" => FlowAnnFunc::Outputs,
+            "lock" => FlowAnnFunc::Lock,
+            "test" => FlowAnnFunc::Test,
+            "should_fail" => FlowAnnFunc::ShouldFail,
             _ => {
                 warn!("UnImpl FlowAnnFunc: {s}",);
                 FlowAnnFunc::UnImpl
@@ -56,6 +78,9 @@ pub type FlowAnnotation = Annotation<FlowAnnFunc>;
 pub trait TransAnno {
     fn undo_flow_name(&self) -> Option<String>;
 }
+pub trait WhenAnno {
+    fn when_express(&self) -> Option<String>;
+}
 pub trait DryrunAnno {
     fn dryrun_flow_name(&self) -> Option<String>;
 }
@@ -70,6 +95,16 @@ impl TransAnno for FlowAnnotation {
     }
 }
 
+impl WhenAnno for FlowAnnotation {
+    fn when_express(&self) -> Option<String> {
+        if self.func == FlowAnnFunc::When {
+            self.get_arg(FST_ARG_TAG)
+        } else {
+            None
+        }
+    }
+}
+
 impl DryrunAnno for FlowAnnotation {
     fn dryrun_flow_name(&self) -> Option<String> {
         if self.func == FlowAnnFunc::Dryrun {
@@ -159,6 +194,7 @@ impl New1<String> for FlowAnnota {
 mod tests {
 
     use super::*;
+    use crate::annotation::SEC_ARG_TAG;
     #[test]
     fn test_flowannota_new() {
         let a = FlowAnnotation::new(
@@ -190,4 +226,54 @@ mod tests {
         let anno = FlowAnnotation::from(dto);
         assert_eq!(anno.dryrun_flow_name(), Some("_dryrun_flow".to_string()));
     }
+    #[test]
+    fn test_anno_parallel() {
+        let dto = FunDto::new("parallel", Vec::new());
+        let anno = FlowAnnotation::from(dto);
+        assert_eq!(anno.func, FlowAnnFunc::Parallel);
+    }
+    #[test]
+    fn test_anno_when() {
+        let dto = FunDto::new("when", [(FST_ARG_TAG, "${A} == 'x'")].to_vec());
+        let anno = FlowAnnotation::from(dto);
+        assert_eq!(anno.func, FlowAnnFunc::When);
+        assert_eq!(anno.when_express(), Some("${A} == 'x'".to_string()));
+    }
+    #[test]
+    fn test_anno_hooks() {
+        let anno = FlowAnnotation::from(FunDto::new("on_failure", Vec::new()));
+        assert_eq!(anno.func, FlowAnnFunc::OnFailure);
+        let anno = FlowAnnotation::from(FunDto::new("always", Vec::new()));
+        assert_eq!(anno.func, FlowAnnFunc::Always);
+    }
+
+    #[test]
+    fn test_anno_inputs() {
+        let anno = FlowAnnotation::from(FunDto::new(
+            "inputs",
+            [(FST_ARG_TAG, "src/**/*.rs"), (SEC_ARG_TAG, "Cargo.lock")].to_vec(),
+        ));
+        assert_eq!(anno.func, FlowAnnFunc::Inputs);
+        assert_eq!(anno.get_arg(SEC_ARG_TAG), Some("Cargo.lock".to_string()));
+        let anno = FlowAnnotation::from(FunDto::new("outputs", Vec::new()));
+        assert_eq!(anno.func, FlowAnnFunc::Outputs);
+    }
+
+    #[test]
+    fn test_anno_lock() {
+        let anno = FlowAnnotation::from(FunDto::new(
+            "lock",
+            [(FST_ARG_TAG, "deploy-prod"), ("timeout", "10m")].to_vec(),
+        ));
+        assert_eq!(anno.func, FlowAnnFunc::Lock);
+        assert_eq!(anno.get_arg("timeout"), Some("10m".to_string()));
+    }
+
+    #[test]
+    fn test_anno_test() {
+        let anno = FlowAnnotation::from(FunDto::new("test", Vec::new()));
+        assert_eq!(anno.func, FlowAnnFunc::Test);
+        let anno = FlowAnnotation::from(FunDto::new("should_fail", Vec::new()));
+        assert_eq!(anno.func, FlowAnnFunc::ShouldFail);
+    }
 }
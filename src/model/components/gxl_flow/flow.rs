@@ -7,21 +7,33 @@ use crate::data::AnnDto;
 use crate::evaluator::VarParser;
 use crate::model::components::prelude::*;
 
-use crate::annotation::{ComUsage, Dryrunable, GetArgValue, TaskMessage, Transaction, FST_ARG_TAG};
+use crate::annotation::{
+    ComUsage, Dryrunable, GetArgValue, Parallelable, TaskMessage, Transaction, FST_ARG_TAG,
+};
+use crate::calculate::{Evaluation, ExpressEnum};
+use crate::execution::cache::{var_refs, FlowCache};
+use crate::execution::lock::LockSpec;
+use crate::execution::retry::RetryPolicy;
 use crate::execution::runnable::AsyncRunnableWithSenderTrait;
+use crate::execution::state::value_to_json;
 use crate::execution::task::Task;
+use crate::execution::trans::UndoFailPolicy;
+use crate::meta::MetaInfo;
 use crate::task_report::task_notification::TaskNotice;
 use crate::task_report::task_rc_config::{build_task_url, report_enable, TaskUrlType};
 use crate::task_report::task_result_report::TaskReport;
 use crate::traits::DependTrait;
 
 use crate::components::gxl_block::BlockNode;
+use crate::util::duration::parse_duration;
 use crate::util::http_handle::{create_and_send_task_notice, send_http_request};
 use crate::util::redirect::{init_redirect_file, read_log_content, seek_log_file_end, ReadSignal};
 use contracts::requires;
 use derive_getters::Getters;
+use orion_error::{ErrorOwe, ToStructError};
 use std::io::Write;
 use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
 
 use super::anno::FlowAnnFunc;
 use super::meta::{FlowMeta, FlowMetaHold};
@@ -30,6 +42,10 @@ use super::meta::{FlowMeta, FlowMetaHold};
 pub struct GxlFlow {
     meta: FlowMeta,
     blocks: Vec<BlockNode>,
+    // #[when(...)] 条件, 解析时生成
+    when: Option<ExpressEnum>,
+    // flow 的源码, 计入增量执行的指纹
+    source: String,
     assembled: bool,
 }
 impl GxlFlow {
@@ -48,12 +64,20 @@ impl GxlFlow {
         self.blocks.push(block);
         self
     }
+    pub fn set_when(&mut self, when: Option<ExpressEnum>) {
+        self.when = when;
+    }
+    pub fn set_source(&mut self, source: String) {
+        self.source = source;
+    }
 }
 
 impl DependTrait<&GxlSpace> for GxlFlow {
     fn assemble(self, mod_name: &str, src: &GxlSpace) -> AResult<Self> {
         debug!(target : "assemble", "will assemble flow {}" , self.meta().name() );
         let mut target = GxlFlow::from(self.meta().clone());
+        target.when = self.when.clone();
+        target.source = self.source.clone();
         let pre_order_flows = self.meta.preorder();
         let mut buffer = Vec::new();
         let mut linked = false;
@@ -163,6 +187,35 @@ impl Transaction for GxlFlow {
     fn undo_hold(&self) -> Option<FlowMetaHold> {
         self.meta().undo_meta().clone()
     }
+
+    /// on_undo_fail 只接受 continue/abort, 其它值使 flow 失败, 不能退化为 continue
+    fn undo_fail_policy(&self) -> ExecResult<UndoFailPolicy> {
+        let Some(value) = self
+            .meta()
+            .annotations()
+            .iter()
+            .find(|ann| ann.func == FlowAnnFunc::Transaction)
+            .and_then(|ann| ann.get_arg("on_undo_fail"))
+        else {
+            return Ok(UndoFailPolicy::default());
+        };
+        UndoFailPolicy::parse(value.as_str()).ok_or_else(|| {
+            ExecReason::Args(format!(
+                "flow {} bad on_undo_fail: {value}, expect continue or abort",
+                self.meta.name()
+            ))
+            .to_err()
+        })
+    }
+}
+
+impl Parallelable for GxlFlow {
+    fn is_parallel(&self) -> bool {
+        self.meta()
+            .annotations()
+            .iter()
+            .any(|ann| ann.func == FlowAnnFunc::Parallel)
+    }
 }
 impl GxlFlow {
     #[requires(self.assembled )]
@@ -328,7 +381,7 @@ impl GxlFlow {
             });
         let sender_option = task_description.as_ref().map(|_| cur_sender.clone());
         let TaskValue { vars, .. } = match block.async_exec(ctx, var_dict, sender_option).await {
-            Ok(TaskValue { vars, rec }) => {
+            Ok(TaskValue { vars, rec, .. }) => {
                 task.clone().append(rec);
                 Self::update_task_with_output(&mut task, &shared_output, start_pos).await?;
 
@@ -365,6 +418,20 @@ impl GxlFlow {
         Ok(())
     }
 
+    /// 被 when 或缓存跳过的 flow 同样上报, 报告中心显示为 Skipped
+    async fn report_skipped(&self, var_dict: &VarSpace, reason: &str) -> Result<(), ExecReason> {
+        let Some(des) = self.task_description(var_dict) else {
+            return Ok(());
+        };
+        if !report_enable().await {
+            return Ok(());
+        }
+        let mut task = Task::from(des);
+        let task_notice = create_and_send_task_notice(&task, &TaskNotice::new()).await?;
+        task.skip(reason.to_string());
+        self.report_task_status(&task, &task_notice).await
+    }
+
     /// 完成日志收集
     async fn finalize_log_collection(
         &self,
@@ -434,6 +501,93 @@ impl GxlFlow {
         }
         false
     }
+    /// #[timeout("10m")]: 时长无法解析时 flow 失败, 不能退化为不限时
+    pub fn timeout(&self) -> ExecResult<Option<Duration>> {
+        let Some(ann) = self
+            .meta
+            .annotations()
+            .iter()
+            .find(|x| x.func == FlowAnnFunc::Timeout)
+        else {
+            return Ok(None);
+        };
+        let value = ann.get_arg(FST_ARG_TAG).unwrap_or_default();
+        parse_duration(value.as_str()).map(Some).ok_or_else(|| {
+            ExecReason::Args(format!("flow {} bad timeout: {value}", self.meta.name())).to_err()
+        })
+    }
+    /// #[retry(count=3, delay="5s", backoff="exp")]: 参数错误时 flow 失败, 不能退化为不重试
+    pub fn retry_policy(&self) -> ExecResult<Option<RetryPolicy>> {
+        let Some(ann) = self
+            .meta
+            .annotations()
+            .iter()
+            .find(|x| x.func == FlowAnnFunc::Retry)
+        else {
+            return Ok(None);
+        };
+        RetryPolicy::from_args(&ann.args).map(Some).ok_or_else(|| {
+            ExecReason::Args(format!(
+                "flow {} bad retry: {:?}",
+                self.meta.name(),
+                ann.args
+            ))
+            .to_err()
+        })
+    }
+    /// #[inputs(...)] #[outputs(...)]: 声明了 inputs 时按文件指纹增量执行,
+    /// 路径中的变量在执行时求值; flow 源码, 选用的 env 与矩阵单元,
+    /// 以及源码中引用的变量同样计入指纹
+    pub fn flow_cache(&self, ctx: &ExecContext, var_dict: &VarSpace) -> Option<FlowCache> {
+        let exp = EnvExpress::from_env_mix(var_dict.global().clone());
+        let collect = |func: FlowAnnFunc| -> Vec<String> {
+            self.meta
+                .annotations()
+                .iter()
+                .filter(|x| x.func == func)
+                .flat_map(|x| FlowCache::positional_args(&x.args))
+                .map(|x| exp.safe_eval(x.as_str()))
+                .collect()
+        };
+        let inputs = collect(FlowAnnFunc::Inputs);
+        if inputs.is_empty() {
+            return None;
+        }
+        // 只计入引用的变量, 密文变量不计入取值
+        let vars: Vec<String> = var_refs(&self.source)
+            .into_iter()
+            .map(|name| {
+                let value = var_dict.get(&name).as_ref().and_then(value_to_json);
+                format!("{name}={}", value.unwrap_or_default())
+            })
+            .collect();
+        let context = format!(
+            "{}\n{}\n{}",
+            self.source,
+            ctx.cache_scope(),
+            vars.join("\n")
+        );
+        Some(FlowCache::new(inputs, collect(FlowAnnFunc::Outputs)).with_context(context))
+    }
+    /// #[lock("name", timeout="10m")]: 锁名中的变量在执行时求值; 参数错误时 flow 失败
+    pub fn lock_spec(&self, var_dict: &VarSpace) -> ExecResult<Option<LockSpec>> {
+        let Some(ann) = self
+            .meta
+            .annotations()
+            .iter()
+            .find(|x| x.func == FlowAnnFunc::Lock)
+        else {
+            return Ok(None);
+        };
+        let mut r_with = WithContext::want("flow lock");
+        r_with.with("flow", self.meta.name());
+        let spec = LockSpec::from_args(&ann.args).with(&r_with)?;
+        let exp = EnvExpress::from_env_mix(var_dict.global().clone());
+        Ok(Some(LockSpec::new(
+            exp.safe_eval(spec.name()),
+            *spec.timeout(),
+        )))
+    }
     pub fn is_auto_entry(&self) -> bool {
         let annotation = self.meta.annotations();
         for ann in annotation {
@@ -445,6 +599,34 @@ impl GxlFlow {
         }
         false
     }
+    /// #[on_failure]: 执行失败后运行
+    pub fn is_on_failure(&self) -> bool {
+        self.meta
+            .annotations()
+            .iter()
+            .any(|x| x.func == FlowAnnFunc::OnFailure)
+    }
+    /// #[always]: 无论成功失败都在最后运行
+    pub fn is_always(&self) -> bool {
+        self.meta
+            .annotations()
+            .iter()
+            .any(|x| x.func == FlowAnnFunc::Always)
+    }
+    /// #[test]: 由 gflow test 运行
+    pub fn is_test(&self) -> bool {
+        self.meta
+            .annotations()
+            .iter()
+            .any(|x| x.func == FlowAnnFunc::Test)
+    }
+    /// #[should_fail]: 测试 flow 执行失败才算通过
+    pub fn is_should_fail(&self) -> bool {
+        self.meta
+            .annotations()
+            .iter()
+            .any(|x| x.func == FlowAnnFunc::ShouldFail)
+    }
     pub fn is_auto_exit(&self) -> bool {
         let annotation = self.meta.annotations();
         for ann in annotation {
@@ -470,12 +652,79 @@ impl AsyncRunnableWithSenderTrait for GxlFlow {
             .unwrap_or(self.meta.name().to_string());
         let mut job = Job::from(&des);
         ctx.append(self.meta.name());
-        let TaskValue { vars, rec, .. } = self.exec_self(ctx.clone(), var_dict, sender).await?;
+        if let Some(when) = &self.when {
+            if !when.decide(ctx.clone(), &var_dict).owe_logic()? {
+                let reason = format!("when: {}", self.meta.when_express().unwrap_or_default());
+                info!(target: ctx.path(), "skip flow {}, {reason}", self.meta.name());
+                self.report_skipped(&var_dict, &reason).await?;
+                let mut task = Task::from(self.meta.name());
+                task.skip(reason);
+                job.append(task);
+                return Ok(TaskValue::from((var_dict, ExecOut::Job(job))));
+            }
+        }
+        let cache = self
+            .flow_cache(&ctx, &var_dict)
+            .filter(|_| !*ctx.dryrun())
+            .map(|x| (self.meta.long_name(), x));
+        let cache_root = FlowCache::cache_root(ctx.cache_dir().as_deref());
+        if let Some((cache_key, cache)) = &cache {
+            if cache.is_up_to_date(&cache_root, cache_key) {
+                info!(target: ctx.path(), "skip flow {}, up-to-date", self.meta.name());
+                self.report_skipped(&var_dict, "up-to-date").await?;
+                let mut task = Task::from(self.meta.name());
+                task.skip("up-to-date".into());
+                job.append(task);
+                return Ok(TaskValue::from((var_dict, ExecOut::Job(job))));
+            }
+        }
+        // 锁覆盖全部重试
+        let _lock = match self.lock_spec(&var_dict)?.filter(|_| !*ctx.plan()) {
+            Some(spec) => spec.acquire().await?,
+            None => None,
+        };
+        let TaskValue { vars, rec, .. } = match self.retry_policy()? {
+            Some(policy) => {
+                policy
+                    .retry_exec(self.meta.name(), || {
+                        self.exec_attempt(ctx.clone(), var_dict.clone(), sender.clone())
+                    })
+                    .await?
+            }
+            None => self.exec_attempt(ctx.clone(), var_dict, sender).await?,
+        };
         var_dict = vars;
         job.append(rec);
+        if let Some((cache_key, cache)) = cache.filter(|_| !*ctx.plan()) {
+            if let Err(e) = cache.save(&cache_root, &cache_key) {
+                warn!(target: ctx.path(), "save flow cache {cache_key} failed: {e}");
+            }
+        }
         Ok(TaskValue::from((var_dict, ExecOut::Job(job))))
     }
 }
+
+impl GxlFlow {
+    // 单次执行, #[timeout] 对每次尝试分别计时
+    async fn exec_attempt(
+        &self,
+        mut ctx: ExecContext,
+        var_dict: VarSpace,
+        sender: Option<mpsc::Sender<ReadSignal>>,
+    ) -> TaskResult {
+        let limit = self.timeout()?;
+        if let Some(dur) = limit {
+            ctx.limit_deadline(dur);
+        }
+        let exec = self.exec_self(ctx, var_dict, sender);
+        match limit {
+            Some(dur) => tokio::time::timeout(dur, exec).await.map_err(|_| {
+                ExecReason::Timeout(self.meta.name().clone(), format!("{dur:?}")).to_err()
+            })?,
+            None => exec.await,
+        }
+    }
+}
 impl ComponentMeta for GxlFlow {
     fn gxl_meta(&self) -> GxlMeta {
         GxlMeta::Flow(self.meta.clone())
@@ -491,9 +740,13 @@ impl AppendAble<BlockNode> for GxlFlow {
 #[cfg(test)]
 mod tests {
 
+    use orion_common::friendly::New3;
     use orion_error::TestAssert;
 
-    use crate::{components::GxlMod, infra::once_init_log, meta::MetaInfo};
+    use crate::{
+        components::{gxl_flow::anno::FlowAnnotation, GxlMod},
+        infra::once_init_log,
+    };
 
     use super::*;
 
@@ -588,4 +841,47 @@ mod tests {
             "test_mod.flow3"
         );
     }
+
+    #[test]
+    fn test_undo_fail_policy() {
+        let policy = |value: &str| {
+            let ann = FlowAnnotation::new(
+                FlowAnnFunc::Transaction,
+                "transaction",
+                vec![("on_undo_fail", value)],
+            );
+            GxlFlow::from(FlowMeta::build_flow("trans").with_annotate(ann)).undo_fail_policy()
+        };
+        assert_eq!(policy("abort").assert(), UndoFailPolicy::Abort);
+        assert_eq!(policy(" continue ").assert(), UndoFailPolicy::Continue);
+        assert!(policy("retry").is_err());
+        let flow = GxlFlow::from("plain");
+        assert_eq!(flow.undo_fail_policy().assert(), UndoFailPolicy::Continue);
+    }
+
+    #[test]
+    fn test_flow_timeout() {
+        let timeout = |value: &str| {
+            let ann = FlowAnnotation::new(FlowAnnFunc::Timeout, "timeout", vec![("_1", value)]);
+            GxlFlow::from(FlowMeta::build_flow("slow").with_annotate(ann)).timeout()
+        };
+        assert_eq!(timeout("10m").assert(), Some(Duration::from_secs(600)));
+        assert!(timeout("ten minutes").is_err());
+        assert_eq!(GxlFlow::from("plain").timeout().assert(), None);
+    }
+
+    #[test]
+    fn test_flow_retry_policy() {
+        let retry = |args: Vec<(&str, &str)>| {
+            let ann = FlowAnnotation::new(FlowAnnFunc::Retry, "retry", args);
+            GxlFlow::from(FlowMeta::build_flow("fetch").with_annotate(ann)).retry_policy()
+        };
+        assert_eq!(
+            retry(vec![("count", "3"), ("delay", "5s")]).assert(),
+            RetryPolicy::parse("count=3,delay=5s")
+        );
+        assert!(retry(vec![("count", "three")]).is_err());
+        assert!(retry(vec![("backoff", "linear")]).is_err());
+        assert_eq!(GxlFlow::from("plain").retry_policy().assert(), None);
+    }
 }
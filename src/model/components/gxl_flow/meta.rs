@@ -7,7 +7,7 @@ use crate::{
 };
 use std::{fmt::Debug, sync::Arc};
 
-use super::anno::{DryrunAnno, FlowAnnotation, TransAnno};
+use super::anno::{DryrunAnno, FlowAnnotation, TransAnno, WhenAnno};
 #[derive(Clone, Getters, Default)]
 pub struct FlowMeta {
     class: GxlType,
@@ -95,6 +95,10 @@ impl FlowMeta {
         None
     }
 
+    pub fn when_express(&self) -> Option<String> {
+        self.annotations.iter().find_map(|ann| ann.when_express())
+    }
+
     pub(crate) fn dryrun_flow_name(&self) -> Option<String> {
         for ann in &self.annotations {
             if ann.dryrun_flow_name().is_some() {
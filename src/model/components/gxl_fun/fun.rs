@@ -3,7 +3,7 @@ use crate::components::gxl_mod::meta::ModMeta;
 use crate::components::gxl_spc::GxlSpace;
 use crate::model::components::prelude::*;
 
-use crate::execution::runnable::{AsyncRunnableArgsTrait, AsyncRunnableWithSenderTrait};
+use crate::execution::runnable::{AsyncRunnableArgsTrait, AsyncRunnableWithSenderTrait, BlockCtrl};
 use crate::execution::task::Task;
 use crate::primitive::GxlAParams;
 use crate::task_report::task_notification::TaskNotice;
@@ -104,13 +104,12 @@ impl GxlFun {
         let cur_vars = var_dict.merge_args_to(self.meta().params(), args)?;
         let task = Task::from(self.meta.name());
         let task_notice = TaskNotice::new();
-        // 执行所有块
-        // TODO : @txy
-        let (_cur_vars, _task) = self
+        // 函数内的变量不回写调用方, 结果通过 return 传出
+        let (_cur_vars, _task, ret) = self
             .execute_blocks(ctx, cur_vars, None, task.clone(), task_notice.clone())
             .await?;
 
-        Ok(TaskValue::from((var_dict, ExecOut::Ignore)))
+        Ok(TaskValue::from((var_dict, ExecOut::Ignore)).with_ctrl(ret))
     }
 
     // 辅助方法：执行所有块
@@ -121,10 +120,11 @@ impl GxlFun {
         task_description: Option<String>,
         mut task: Task,
         task_notice: TaskNotice,
-    ) -> Result<(VarSpace, Task), ExecError> {
+    ) -> Result<(VarSpace, Task, Option<BlockCtrl>), ExecError> {
+        let mut ret = None;
         for item in &self.blocks {
             if task_description.is_some() && report_enable().await {
-                let (var_dict_new, task_new) = self
+                let (var_dict_new, task_new, ctrl) = self
                     .execute_block_with_monitoring(
                         item,
                         ctx.clone(),
@@ -136,15 +136,22 @@ impl GxlFun {
                     .await?;
                 var_dict = var_dict_new;
                 task = task_new;
+                ret = ctrl;
             } else {
-                let TaskValue { vars, rec, .. } =
+                let TaskValue { vars, rec, ctrl } =
                     item.async_exec(ctx.clone(), var_dict, None).await?;
                 var_dict = vars;
                 task.append(rec);
+                ret = ctrl;
+            }
+            if matches!(ret, Some(BlockCtrl::Return(_))) {
+                break;
             }
         }
         task.finish();
-        Ok((var_dict, task))
+        // 只有 return 可以离开函数
+        let ret = ret.filter(|x| matches!(x, BlockCtrl::Return(_)));
+        Ok((var_dict, task, ret))
     }
 
     // 辅助方法：执行单个块并监控日志
@@ -156,7 +163,7 @@ impl GxlFun {
         task_description: Option<String>,
         mut task: Task,
         task_notice: TaskNotice,
-    ) -> Result<(VarSpace, Task), ExecError> {
+    ) -> Result<(VarSpace, Task, Option<BlockCtrl>), ExecError> {
         let (cur_sender, receiver) = mpsc::channel::<ReadSignal>();
         let log_file = init_redirect_file()?;
         let start_pos = Arc::new(Mutex::new(seek_log_file_end(&log_file)?));
@@ -207,7 +214,7 @@ impl GxlFun {
                 Ok(())
             });
         let sender_option = task_description.as_ref().map(|_| cur_sender.clone());
-        let TaskValue { vars, rec, .. } = block.async_exec(ctx, var_dict, sender_option).await?;
+        let TaskValue { vars, rec, ctrl } = block.async_exec(ctx, var_dict, sender_option).await?;
 
         drop(cur_sender);
         drop(monitor_handle);
@@ -219,7 +226,7 @@ impl GxlFun {
             self.report_task_status(&task, &task_notice).await?;
         }
 
-        Ok((vars, task))
+        Ok((vars, task, ctrl))
     }
 
     /// 报告任务状态
@@ -268,9 +275,9 @@ impl AsyncRunnableArgsTrait for GxlFun {
     ) -> TaskResult {
         let action = Action::from("gx.cmd");
         ctx.append(self.meta.name());
-        let TaskValue { vars, .. } = self.exec_self(ctx.clone(), vars_dict, args).await?;
+        let TaskValue { vars, ctrl, .. } = self.exec_self(ctx.clone(), vars_dict, args).await?;
         vars_dict = vars;
-        Ok(TaskValue::from((vars_dict, ExecOut::Action(action))))
+        Ok(TaskValue::from((vars_dict, ExecOut::Action(action))).with_ctrl(ctrl))
     }
 }
 impl ComponentMeta for GxlFun {
@@ -0,0 +1,228 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use indexmap::IndexMap;
+
+use crate::components::gxl_flow::meta::FlowMeta;
+use crate::components::gxl_spc::GxlSpace;
+use crate::meta::MetaInfo;
+
+/// flow 之间的关系
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EdgeKind {
+    Pre,
+    Post,
+    Entry,
+    Exit,
+    Undo,
+    Dryrun,
+    OnFailure,
+    Always,
+}
+
+impl EdgeKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            EdgeKind::Pre => "pre",
+            EdgeKind::Post => "post",
+            EdgeKind::Entry => "entry",
+            EdgeKind::Exit => "exit",
+            EdgeKind::Undo => "undo",
+            EdgeKind::Dryrun => "dryrun",
+            EdgeKind::OnFailure => "on_failure",
+            EdgeKind::Always => "always",
+        }
+    }
+    // 顺序执行以外的关系用虚线
+    fn is_dashed(&self) -> bool {
+        !matches!(self, EdgeKind::Pre | EdgeKind::Post)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Getters)]
+pub struct GraphEdge {
+    from: String,
+    to: String,
+    kind: EdgeKind,
+}
+
+/// mod/flow 关系图: 节点为 mod 名与 flow 全名(mod.flow)
+#[derive(Debug, Clone, Default, Getters)]
+pub struct FlowGraph {
+    mods: IndexMap<String, Vec<String>>,
+    edges: Vec<GraphEdge>,
+}
+
+impl FlowGraph {
+    pub fn build(spc: &GxlSpace) -> Self {
+        let mut graph = Self::default();
+        for (mod_name, mox) in spc.mods() {
+            let mut flows = Vec::new();
+            for flow in mox.flows().values() {
+                let meta = flow.meta();
+                let name = meta.long_name();
+                for pre in meta.pre_metas() {
+                    graph.link(pre.long_name(), name.clone(), EdgeKind::Pre);
+                }
+                for pos in meta.pos_metas() {
+                    graph.link(name.clone(), pos.long_name(), EdgeKind::Post);
+                }
+                if let Some(undo) = meta.undo_meta() {
+                    graph.link(name.clone(), undo.long_name(), EdgeKind::Undo);
+                }
+                if let Some(dryrun) = meta.dryrun_meta() {
+                    graph.link(name.clone(), dryrun.long_name(), EdgeKind::Dryrun);
+                }
+                flows.push(name);
+            }
+            if flows.is_empty() {
+                continue;
+            }
+            let mod_links = [
+                (mox.entrys(), EdgeKind::Entry),
+                (mox.exits(), EdgeKind::Exit),
+                (mox.fail_hooks(), EdgeKind::OnFailure),
+                (mox.always_hooks(), EdgeKind::Always),
+            ];
+            for (metas, kind) in mod_links {
+                for meta in metas {
+                    graph.link(mod_name.clone(), meta.long_name(), kind);
+                }
+            }
+            graph.mods.insert(mod_name.clone(), flows);
+        }
+        graph
+    }
+
+    fn link(&mut self, from: String, to: String, kind: EdgeKind) {
+        let edge = GraphEdge { from, to, kind };
+        if !self.edges.contains(&edge) {
+            self.edges.push(edge);
+        }
+    }
+
+    /// 只保留给定 flow 及其 undo/dryrun 目标与 hook, 用于 `gflow graph <flow>`
+    pub fn retain_flows(&mut self, metas: &[FlowMeta]) {
+        let mut keep: HashSet<String> = metas.iter().map(|x| x.long_name()).collect();
+        for edge in &self.edges {
+            let extra = match edge.kind {
+                EdgeKind::Undo | EdgeKind::Dryrun => keep.contains(&edge.from),
+                EdgeKind::OnFailure | EdgeKind::Always => true,
+                _ => false,
+            };
+            if extra {
+                keep.insert(edge.to.clone());
+            }
+        }
+        for flows in self.mods.values_mut() {
+            flows.retain(|x| keep.contains(x));
+        }
+        self.mods.retain(|_, flows| !flows.is_empty());
+        let mods = &self.mods;
+        self.edges.retain(|x| {
+            keep.contains(&x.to) && (keep.contains(&x.from) || mods.contains_key(&x.from))
+        });
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "digraph gflow {{");
+        let _ = writeln!(out, "    rankdir=LR;");
+        for (mod_name, flows) in &self.mods {
+            let _ = writeln!(out, "    subgraph \"cluster_{mod_name}\" {{");
+            let _ = writeln!(out, "        label=\"{mod_name}\";");
+            let _ = writeln!(out, "        \"{mod_name}\" [shape=folder];");
+            for flow in flows {
+                let _ = writeln!(out, "        \"{flow}\" [label=\"{}\"];", short_name(flow));
+            }
+            let _ = writeln!(out, "    }}");
+        }
+        for edge in &self.edges {
+            let style = if edge.kind.is_dashed() {
+                ", style=dashed"
+            } else {
+                ""
+            };
+            let _ = writeln!(
+                out,
+                "    \"{}\" -> \"{}\" [label=\"{}\"{style}];",
+                edge.from,
+                edge.to,
+                edge.kind.label()
+            );
+        }
+        let _ = writeln!(out, "}}");
+        out
+    }
+
+    pub fn to_mermaid(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "flowchart LR");
+        for (mod_name, flows) in &self.mods {
+            let _ = writeln!(
+                out,
+                "    subgraph {}_mod [\"{mod_name}\"]",
+                node_id(mod_name)
+            );
+            let _ = writeln!(out, "        {}[/\"{mod_name}\"/]", node_id(mod_name));
+            for flow in flows {
+                let _ = writeln!(out, "        {}[\"{}\"]", node_id(flow), short_name(flow));
+            }
+            let _ = writeln!(out, "    end");
+        }
+        for edge in &self.edges {
+            let arrow = if edge.kind.is_dashed() { "-.->" } else { "-->" };
+            let _ = writeln!(
+                out,
+                "    {} {arrow}|{}| {}",
+                node_id(&edge.from),
+                edge.kind.label(),
+                node_id(&edge.to)
+            );
+        }
+        out
+    }
+}
+
+fn short_name(flow: &str) -> &str {
+    flow.split_once('.').map(|x| x.1).unwrap_or(flow)
+}
+
+// mermaid 节点 id 只允许字母数字与下划线
+fn node_id(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> FlowGraph {
+        let mut graph = FlowGraph::default();
+        graph.mods.insert(
+            "main".into(),
+            vec!["main.build".into(), "main.test".into(), "main._undo".into()],
+        );
+        graph.link("main.build".into(), "main.test".into(), EdgeKind::Post);
+        graph.link("main.build".into(), "main._undo".into(), EdgeKind::Undo);
+        graph.link("main".into(), "main.build".into(), EdgeKind::Entry);
+        graph
+    }
+
+    #[test]
+    fn test_graph_render() {
+        let graph = sample();
+        let dot = graph.to_dot();
+        assert!(dot.contains("subgraph \"cluster_main\""));
+        assert!(dot.contains("\"main.build\" -> \"main.test\" [label=\"post\"];"));
+        assert!(dot.contains("\"main.build\" -> \"main._undo\" [label=\"undo\", style=dashed];"));
+
+        let mermaid = graph.to_mermaid();
+        assert!(mermaid.starts_with("flowchart LR\n"));
+        assert!(mermaid.contains("main_build[\"build\"]"));
+        assert!(mermaid.contains("main_build -->|post| main_test"));
+        assert!(mermaid.contains("main -.->|entry| main_build"));
+    }
+}
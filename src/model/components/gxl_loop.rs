@@ -1,33 +1,282 @@
 use std::sync::mpsc::Sender;
+use std::sync::Arc;
 
-use orion_error::ToStructError;
+use tokio::sync::Semaphore;
+
+use orion_error::{ErrorOwe, ToStructError};
 
 use super::prelude::*;
 use crate::{
     ability::prelude::TaskValue,
-    execution::{runnable::AsyncRunnableWithSenderTrait, task::Task},
-    sec::SecValueType,
+    calculate::{Evaluation, ExpressEnum},
+    execution::{
+        runnable::{AsyncRunnableWithSenderTrait, BlockCtrl},
+        task::Task,
+    },
+    primitive::GxlObject,
+    sec::{SecFrom, SecValueType},
     traits::Setter,
     util::redirect::ReadSignal,
 };
 
 use super::gxl_block::BlockNode;
 
+/// while 循环未指定 max 时的迭代上限
+pub const WHILE_MAX_LOOP: u32 = 1000;
+
+#[derive(Clone)]
+pub enum LoopKind {
+    // for x in ${SET}
+    Each(String),
+    // for i in range(beg, end), 不含 end
+    Range(GxlObject, GxlObject),
+    // while <cond> max N
+    While(ExpressEnum, u32),
+}
+
 #[derive(Clone, Getters)]
 pub struct GxlLoop {
     cur_name: String,
-    var_name: String,
+    // for k, v in ${MAP} 中的 key, 列表时为下标
+    key_name: Option<String>,
+    kind: LoopKind,
     body: BlockNode,
+    // parallel(N): 循环体并发执行的上限
+    parallel: Option<usize>,
 }
 
 impl GxlLoop {
     pub fn new(cur_name: String, dct_name: String, body: BlockNode) -> Self {
         Self {
             cur_name,
-            var_name: dct_name,
+            key_name: None,
+            kind: LoopKind::Each(dct_name),
+            body,
+            parallel: None,
+        }
+    }
+    pub fn with_key(mut self, key_name: Option<String>) -> Self {
+        self.key_name = key_name;
+        self
+    }
+    pub fn range(cur_name: String, beg: GxlObject, end: GxlObject, body: BlockNode) -> Self {
+        Self {
+            cur_name,
+            key_name: None,
+            kind: LoopKind::Range(beg, end),
+            body,
+            parallel: None,
+        }
+    }
+    pub fn while_loop(cond: ExpressEnum, max: u32, body: BlockNode) -> Self {
+        Self {
+            cur_name: String::new(),
+            key_name: None,
+            kind: LoopKind::While(cond, max),
             body,
+            parallel: None,
+        }
+    }
+
+    pub fn with_body(mut self, body: BlockNode) -> Self {
+        self.body = body;
+        self
+    }
+    pub fn with_parallel(mut self, parallel: Option<usize>) -> Self {
+        self.parallel = parallel;
+        self
+    }
+
+    // 执行一次循环体, 返回 break/continue/return
+    async fn exec_body(
+        &self,
+        ctx: ExecContext,
+        dict: VarSpace,
+        sender: Option<Sender<ReadSignal>>,
+        task: &mut Task,
+    ) -> ExecResult<(VarSpace, Option<BlockCtrl>)> {
+        let TaskValue { vars, rec, ctrl } = self.body.async_exec(ctx, dict, sender).await?;
+        task.append(rec);
+        Ok((vars, ctrl))
+    }
+
+    // 循环项 (key, value): obj 为键, list 为下标, range 为数值本身
+    fn loop_items(&self, dict: &VarSpace) -> ExecResult<Vec<(SecValueType, SecValueType)>> {
+        match self.kind() {
+            LoopKind::Each(var_name) => match dict.get(var_name) {
+                Some(SecValueType::Obj(obj)) => Ok(obj
+                    .into_iter()
+                    .map(|(k, v)| (SecValueType::nor_from(k.to_string()), v))
+                    .collect()),
+                Some(SecValueType::List(list)) => Ok(list
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, v)| (SecValueType::nor_from(i as u64), v))
+                    .collect()),
+                Some(_) => {
+                    ExecReason::Bug(format!("loop only support obj,list {var_name}")).err_result()
+                }
+                None => ExecReason::Miss(var_name.into()).err_result(),
+            },
+            LoopKind::Range(beg, end) => {
+                let beg = range_bound(beg, dict)?;
+                let end = range_bound(end, dict)?;
+                Ok((beg..end)
+                    .map(|i| (SecValueType::nor_from(i), SecValueType::nor_from(i)))
+                    .collect())
+            }
+            LoopKind::While(..) => Ok(Vec::new()),
         }
     }
+
+    fn bind_item(&self, dict: &mut VarSpace, key: SecValueType, item: SecValueType) {
+        if let Some(key_name) = self.key_name() {
+            dict.global_mut().set(key_name.clone(), key);
+        }
+        dict.global_mut().set(self.cur_name().clone(), item);
+    }
+
+    async fn exec_items(
+        &self,
+        ctx: ExecContext,
+        dict: VarSpace,
+        sender: Option<Sender<ReadSignal>>,
+        items: Vec<(SecValueType, SecValueType)>,
+        task: &mut Task,
+    ) -> ExecResult<(VarSpace, Option<BlockCtrl>)> {
+        let mut cur_dict = dict;
+        for (key, item) in items {
+            self.bind_item(&mut cur_dict, key, item);
+            let (vars, ctrl) = self
+                .exec_body(ctx.clone(), cur_dict, sender.clone(), task)
+                .await?;
+            cur_dict = vars;
+            match ctrl {
+                Some(BlockCtrl::Break) => break,
+                Some(BlockCtrl::Return(_)) => return Ok((cur_dict, ctrl)),
+                _ => {}
+            }
+        }
+        Ok((cur_dict, None))
+    }
+
+    /// 并发执行各循环项, 同时运行的数量不超过 limit;
+    /// 每项都从同一份 VarSpace 出发, 结束后按顺序合并变量变更与 Task 记录;
+    /// 失败项等待全部结束后汇总返回, break/return 只结束当前项
+    async fn exec_parallel(
+        &self,
+        ctx: ExecContext,
+        dict: VarSpace,
+        sender: Option<Sender<ReadSignal>>,
+        items: Vec<(SecValueType, SecValueType)>,
+        limit: usize,
+        task: &mut Task,
+    ) -> ExecResult<VarSpace> {
+        let semaphore = Arc::new(Semaphore::new(limit.max(1)));
+        let mut handles = Vec::new();
+        for (key, item) in items {
+            let name = format!("loop[{key}]");
+            let mut item_dict = dict.clone();
+            self.bind_item(&mut item_dict, key, item);
+            let body = self.body.clone();
+            let ctx = ctx.clone();
+            let sender = sender.clone();
+            let semaphore = semaphore.clone();
+            let handle = tokio::spawn(async move {
+                let _permit = semaphore.acquire_owned().await;
+                body.async_exec(ctx, item_dict, sender).await
+            });
+            handles.push((name, handle));
+        }
+        let mut cur_dict = dict.clone();
+        let mut failed = Vec::new();
+        let mut interrupted = None;
+        for (name, handle) in handles {
+            let mut item_task = Task::from(name.clone());
+            match handle.await {
+                Ok(Ok(TaskValue { vars, rec, .. })) => {
+                    cur_dict.merge_branch(&dict, vars);
+                    item_task.append(rec);
+                    item_task.finish();
+                }
+                Ok(Err(e)) => {
+                    warn!(target: ctx.path(), "{name} failed: {e}");
+                    item_task.err(e.to_string());
+                    if matches!(e.reason(), ExecReason::Interrupted(_)) {
+                        interrupted.get_or_insert(e);
+                    } else {
+                        failed.push(format!("{name}: {e}"));
+                    }
+                }
+                Err(e) => {
+                    item_task.err(e.to_string());
+                    failed.push(format!("{name}: panic {e}"));
+                }
+            }
+            task.append(item_task);
+        }
+        if let Some(e) = interrupted {
+            return Err(e);
+        }
+        if !failed.is_empty() {
+            return ExecReason::Check(format!(
+                "parallel loop failed {} items: {}",
+                failed.len(),
+                failed.join("; ")
+            ))
+            .err_result();
+        }
+        Ok(cur_dict)
+    }
+
+    async fn exec_while(
+        &self,
+        ctx: ExecContext,
+        dict: VarSpace,
+        sender: Option<Sender<ReadSignal>>,
+        (cond, max): (&ExpressEnum, u32),
+        task: &mut Task,
+    ) -> ExecResult<(VarSpace, Option<BlockCtrl>)> {
+        let mut cur_dict = dict;
+        let mut count = 0;
+        while cond.decide(ctx.clone(), &cur_dict).owe_logic()? {
+            if count >= max {
+                return ExecReason::Check(format!("while loop exceed max {max}")).err_result();
+            }
+            count += 1;
+            let (vars, ctrl) = self
+                .exec_body(ctx.clone(), cur_dict, sender.clone(), task)
+                .await?;
+            cur_dict = vars;
+            match ctrl {
+                Some(BlockCtrl::Break) => break,
+                Some(BlockCtrl::Return(_)) => return Ok((cur_dict, ctrl)),
+                _ => {}
+            }
+        }
+        Ok((cur_dict, None))
+    }
+}
+
+fn range_bound(obj: &GxlObject, dict: &VarSpace) -> ExecResult<u64> {
+    let value = match obj {
+        GxlObject::VarRef(name) => dict
+            .get(name.as_str())
+            .ok_or(ExecReason::Miss(name.clone()).to_err())?,
+        GxlObject::Value(value) => value.clone(),
+    };
+    match &value {
+        SecValueType::Number(v) => Ok(*v.value()),
+        SecValueType::Float(v) if v.value().fract() == 0.0 && *v.value() >= 0.0 => {
+            Ok(*v.value() as u64)
+        }
+        SecValueType::String(v) => v
+            .value()
+            .trim()
+            .parse::<u64>()
+            .map_err(|_| ExecReason::Args(format!("range need number: {}", v.value())).to_err()),
+        _ => ExecReason::Args(format!("range need number: {value}")).err_result(),
+    }
 }
 
 #[async_trait]
@@ -39,46 +288,26 @@ impl AsyncRunnableWithSenderTrait for GxlLoop {
         sender: Option<Sender<ReadSignal>>,
     ) -> TaskResult {
         let mut task = Task::from("loop");
-        let mut cur_dict = dict.clone();
-        if let Some(found) = dict.get(self.var_name()) {
-            match found {
-                SecValueType::Obj(obj) => {
-                    for (_k, v) in obj.iter() {
-                        cur_dict
-                            .global_mut()
-                            .set(self.cur_name().clone(), v.clone());
-                        let TaskValue { vars, rec, .. } = self
-                            .body
-                            .async_exec(ctx.clone(), cur_dict, sender.clone())
-                            .await?;
-                        cur_dict = vars;
-                        task.append(rec);
-                    }
-                }
-                SecValueType::List(list) => {
-                    for i in list.iter() {
-                        cur_dict
-                            .global_mut()
-                            .set(self.cur_name().clone(), i.clone());
-                        let TaskValue { vars, rec, .. } = self
-                            .body
-                            .async_exec(ctx.clone(), cur_dict, sender.clone())
+        let (cur_dict, ctrl) = match self.kind() {
+            LoopKind::While(cond, max) => {
+                self.exec_while(ctx, dict, sender, (cond, *max), &mut task)
+                    .await?
+            }
+            _ => {
+                let items = self.loop_items(&dict)?;
+                match self.parallel {
+                    Some(limit) => {
+                        let vars = self
+                            .exec_parallel(ctx, dict, sender, items, limit, &mut task)
                             .await?;
-                        cur_dict = vars;
-                        task.append(rec);
+                        (vars, None)
                     }
-                }
-                _ => {
-                    return ExecReason::Bug(format!(
-                        "loop only support obj,list {}",
-                        self.var_name()
-                    ))
-                    .err_result()
+                    None => self.exec_items(ctx, dict, sender, items, &mut task).await?,
                 }
             }
-            return Ok(TaskValue::from((cur_dict, ExecOut::Task(task))));
-        }
-        ExecReason::Miss(self.var_name().into()).err_result()
+        };
+        // return 继续向外传递
+        Ok(TaskValue::from((cur_dict, ExecOut::Task(task))).with_ctrl(ctrl))
     }
 }
 
@@ -86,7 +315,8 @@ impl AsyncRunnableWithSenderTrait for GxlLoop {
 mod tests {
     use super::*;
     use crate::{
-        ability::GxEcho,
+        ability::{GxCmd, GxEcho},
+        calculate::CmpExpress,
         components::gxl_block::BlockAction,
         model::components::gxl_block::BlockNode,
         sec::{SecFrom, SecValueObj, ToUniCase},
@@ -245,4 +475,159 @@ mod tests {
             "value1" // 最后一次循环设置的值
         );
     }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_loop_range_break(
+        #[from(test_ctx)] ctx: ExecContext,
+        #[from(test_dict)] dict: VarSpace,
+    ) {
+        let mut body = BlockNode::new();
+        body.append(BlockAction::from(GxEcho::new("loop: ${I}")));
+        let loop_node = GxlLoop::range(
+            "i".to_string(),
+            GxlObject::Value(SecValueType::nor_from(1u64)),
+            GxlObject::from_val("4"),
+            body,
+        );
+        let TaskValue { vars, .. } = loop_node
+            .async_exec(ctx.clone(), dict.clone(), None)
+            .await
+            .assert("range");
+        assert_eq!(vars.get("i"), Some(SecValueType::nor_from(3u64)));
+
+        let mut body = BlockNode::new();
+        body.append(BlockAction::Ctrl(BlockCtrl::Break));
+        body.append(BlockAction::from(GxEcho::new("never")));
+        let loop_node = GxlLoop::range(
+            "i".to_string(),
+            GxlObject::from_val("1"),
+            GxlObject::from_val("4"),
+            body,
+        );
+        let TaskValue { vars, ctrl, .. } =
+            loop_node.async_exec(ctx, dict, None).await.assert("range");
+        assert_eq!(vars.get("i"), Some(SecValueType::nor_from(1u64)));
+        assert_eq!(ctrl, None);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_loop_while(
+        #[from(test_ctx)] ctx: ExecContext,
+        #[from(test_dict)] mut dict: VarSpace,
+    ) {
+        dict.global_mut().set("flag", "on");
+        let cond = ExpressEnum::Cmp(CmpExpress::eq(
+            GxlObject::VarRef("flag".into()),
+            GxlObject::from_val("on"),
+        ));
+        let mut body = BlockNode::new();
+        body.append(BlockAction::Ctrl(BlockCtrl::Break));
+        let loop_node = GxlLoop::while_loop(cond.clone(), 3, body);
+        loop_node
+            .async_exec(ctx.clone(), dict.clone(), None)
+            .await
+            .assert("while break");
+
+        let loop_node = GxlLoop::while_loop(cond, 3, BlockNode::new());
+        let err = loop_node.async_exec(ctx, dict, None).await.unwrap_err();
+        assert!(matches!(err.reason(), ExecReason::Check(..)));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_loop_key_binding(
+        #[from(test_ctx)] ctx: ExecContext,
+        #[from(test_dict)] mut dict: VarSpace,
+    ) {
+        let mut obj = SecValueObj::default();
+        obj.insert(
+            "Svc_A".to_unicase(),
+            SecValueType::nor_from("8080".to_string()),
+        );
+        obj.insert(
+            "svc_b".to_unicase(),
+            SecValueType::nor_from("9090".to_string()),
+        );
+        dict.global_mut()
+            .set("services".to_string(), SecValueType::from(obj));
+        dict.global_mut().set(
+            "ports".to_string(),
+            SecValueType::List(vec![
+                SecValueType::nor_from("a".to_string()),
+                SecValueType::nor_from("b".to_string()),
+            ]),
+        );
+
+        let mut body = BlockNode::new();
+        body.append(BlockAction::from(GxEcho::new("${K}: ${V}")));
+        let loop_node =
+            GxlLoop::new("v".into(), "services".into(), body.clone()).with_key(Some("k".into()));
+        let TaskValue { vars, .. } = loop_node
+            .async_exec(ctx.clone(), dict.clone(), None)
+            .await
+            .assert("obj key");
+        assert_eq!(
+            vars.get("k"),
+            Some(SecValueType::nor_from("svc_b".to_string()))
+        );
+
+        let loop_node = GxlLoop::new("v".into(), "ports".into(), body).with_key(Some("k".into()));
+        let TaskValue { vars, .. } = loop_node
+            .async_exec(ctx, dict, None)
+            .await
+            .assert("list index");
+        assert_eq!(vars.get("k"), Some(SecValueType::nor_from(1u64)));
+        assert_eq!(vars.get("v"), Some(SecValueType::nor_from("b".to_string())));
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_loop_parallel(
+        #[from(test_ctx)] ctx: ExecContext,
+        #[from(test_dict)] mut dict: VarSpace,
+    ) {
+        let items = ["a", "b", "c", "d"]
+            .iter()
+            .map(|x| SecValueType::nor_from(x.to_string()))
+            .collect();
+        dict.global_mut()
+            .set("items".to_string(), SecValueType::List(items));
+
+        // 4 项各 sleep 0.2s: 串行至少 0.8s, 并发数为 4 时应在 0.2s 左右完成
+        let mut body = BlockNode::new();
+        body.append(BlockAction::from(GxCmd::new("sleep 0.2".into())));
+        let serial =
+            GxlLoop::new("cur".into(), "items".into(), body.clone()).with_parallel(Some(1));
+        let begin = std::time::Instant::now();
+        serial
+            .async_exec(ctx.clone(), dict.clone(), None)
+            .await
+            .assert("serial");
+        let serial_cost = begin.elapsed();
+        assert!(serial_cost >= std::time::Duration::from_millis(800));
+
+        let loop_node = GxlLoop::new("cur".into(), "items".into(), body).with_parallel(Some(4));
+        let begin = std::time::Instant::now();
+        let TaskValue { vars, .. } = loop_node
+            .async_exec(ctx.clone(), dict.clone(), None)
+            .await
+            .assert("parallel");
+        let cost = begin.elapsed();
+        assert!(cost < std::time::Duration::from_millis(600), "{cost:?}");
+        assert!(cost * 2 < serial_cost);
+        assert_eq!(
+            vars.get("cur"),
+            Some(SecValueType::nor_from("d".to_string()))
+        );
+
+        let mut body = BlockNode::new();
+        body.append(BlockAction::from(GxCmd::new(
+            "test ${CUR} != b && test ${CUR} != d".into(),
+        )));
+        let loop_node = GxlLoop::new("cur".into(), "items".into(), body).with_parallel(Some(2));
+        let err = loop_node.async_exec(ctx, dict, None).await.unwrap_err();
+        assert!(err.to_string().contains("failed 2 items"));
+    }
 }
@@ -53,6 +53,9 @@ pub struct GxlMod {
     funs: IndexMap<String, GxlFun>,
     entrys: Vec<FlowMeta>,
     exits: Vec<FlowMeta>,
+    // #[on_failure] / #[always] flow
+    fail_hooks: Vec<FlowMeta>,
+    always_hooks: Vec<FlowMeta>,
     acts: IndexMap<String, Activity>,
     assembled: bool,
 }
@@ -108,6 +111,12 @@ impl DependTrait<&GxlSpace> for GxlMod {
                     ins.exits.push(pos.clone());
                 }
             }
+            if ass_flow.is_on_failure() {
+                ins.fail_hooks.push(ass_flow.meta().clone());
+            }
+            if ass_flow.is_always() {
+                ins.always_hooks.push(ass_flow.meta().clone());
+            }
             ins.flows.insert(k.clone(), ass_flow);
         }
         for (k, act) in self.acts {
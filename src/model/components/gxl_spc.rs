@@ -1,19 +1,31 @@
-use super::{gxl_flow::meta::FlowMeta, prelude::*};
+use super::{gxl_flow::meta::FlowMeta, gxl_graph::FlowGraph, prelude::*};
 use crate::{
     ability::prelude::TaskValue,
+    const_val::gxl_const,
     execution::{
+        matrix::{matrix_summary, MatrixCell},
         sequence::{ExecSequence, SequAppender, SequLoader},
+        state::RunStore,
+        task::Task,
+        testing::{TestCase, TestReport},
+        trans::{report_rollback, UndoFailPolicy},
         unit::{RunUnitGuard, RunUnitLable},
     },
     menu::*,
     meta::MetaInfo,
-    util::{redirect::ReadSignal, task_report::task_local_report},
+    traits::Setter,
+    util::{executor::SharedExecutor, redirect::ReadSignal, task_report::task_local_report},
 };
 use colored::Colorize;
 use contracts::requires;
 use indexmap::IndexMap;
 use orion_error::ErrorConv;
-use std::{fmt::Display, sync::mpsc::Sender};
+use std::{
+    fmt::Display,
+    path::PathBuf,
+    sync::{mpsc::Sender, Arc},
+    time::Instant,
+};
 
 use super::GxlMod;
 
@@ -178,19 +190,66 @@ fn parse_obj_path(obj_path: &str) -> ExecResult<(&str, &str)> {
 #[derive(Default, Debug, Clone)]
 pub struct ExecOptions {
     dryrun: bool,
-    out: bool,
+    plan: bool,
+    out: Option<bool>,
+    store: Option<RunStore>,
+    executor: SharedExecutor,
+    cache_dir: Option<PathBuf>,
 }
 impl ExecOptions {
     pub fn with_dryrun(mut self, dryrun: bool) -> Self {
         self.dryrun = dryrun;
         self
     }
+    /// 只打印将要执行的命令, 不产生副作用
+    pub fn with_plan(mut self, plan: bool) -> Self {
+        self.plan = plan;
+        self
+    }
+    pub fn with_out(mut self, out: Option<bool>) -> Self {
+        self.out = out;
+        self
+    }
+    /// 将执行进度保存到 store, 用于 --resume
+    pub fn with_store(mut self, store: RunStore) -> Self {
+        self.store = Some(store);
+        self
+    }
+    /// 外部命令的执行器, 用于 --record / --replay
+    pub fn with_executor(mut self, executor: SharedExecutor) -> Self {
+        self.executor = executor;
+        self
+    }
+    /// 增量执行指纹的目录, 一般为 gxl 配置文件所在目录下的 .cache
+    pub fn with_cache_dir(mut self, cache_dir: PathBuf) -> Self {
+        self.cache_dir = Some(cache_dir);
+        self
+    }
     pub fn is_dryrun(&self) -> bool {
         self.dryrun
     }
-    pub fn is_out(&self) -> bool {
+    pub fn is_plan(&self) -> bool {
+        self.plan
+    }
+    pub fn out(&self) -> Option<bool> {
         self.out
     }
+    pub fn store(&self) -> Option<&RunStore> {
+        self.store.as_ref()
+    }
+    pub fn executor(&self) -> &SharedExecutor {
+        &self.executor
+    }
+    pub fn cache_dir(&self) -> Option<&PathBuf> {
+        self.cache_dir.as_ref()
+    }
+}
+
+/// 失败的 flow, 错误信息与失败时的变量, 供 #[on_failure] hook 使用
+struct FlowFailure {
+    flow: String,
+    err: String,
+    vars: VarSpace,
 }
 
 impl GxlSpace {
@@ -203,10 +262,24 @@ impl GxlSpace {
         dryrun: bool,
         var_space: VarSpace,
         sender: Option<Sender<ReadSignal>>,
+    ) -> RunResult<()> {
+        let opts = ExecOptions::default().with_out(out).with_dryrun(dryrun);
+        self.exec_opts(envs_name, flows_name, opts, var_space, sender)
+            .await
+    }
+
+    #[requires(self.assembled)]
+    pub async fn exec_opts<VS: Into<Vec<String>>>(
+        &self,
+        envs_name: VS,
+        flows_name: VS,
+        opts: ExecOptions,
+        var_space: VarSpace,
+        sender: Option<Sender<ReadSignal>>,
     ) -> RunResult<()> {
         info!(
             target: "execution",
-            "Starting execution stack with output: {:?}", out
+            "Starting execution stack with output: {:?}", opts.out()
         );
 
         let envs: Vec<String> = envs_name.into();
@@ -216,28 +289,169 @@ impl GxlSpace {
         warn!(target : "exec","inherted vars :\n{}", var_space.inherited());
         info!(target : "exec","inherted vars :\n{}", var_space.global());
 
-        let main_ctx = ExecContext::new(out, dryrun);
+        let main_ctx = ExecContext::new(opts.out(), opts.is_dryrun())
+            .with_plan(opts.is_plan())
+            .with_executor(opts.executor().clone())
+            .with_cache_dir(opts.cache_dir().cloned());
+        let mut failure = None;
+        let mut result = Ok(());
         for flow_name in flow_names {
-            self.execute_flow(&main_ctx, &var_space, &envs, &flow_name, sender.clone())
-                .await?;
+            if let Err(e) = self
+                .execute_flow(
+                    &main_ctx,
+                    &var_space,
+                    &envs,
+                    &flow_name,
+                    opts.store(),
+                    sender.clone(),
+                    &mut failure,
+                )
+                .await
+            {
+                result = Err(e);
+                break;
+            }
         }
+        // hook 在全部 flow 结束后执行一次
+        self.run_hooks(&main_ctx, &var_space, &envs, failure, sender)
+            .await;
+        result
+    }
 
-        Ok(())
+    /// 矩阵执行: 每个单元以独立的 VarSpace 运行同一组 flow, 每单元记录为一个 Job;
+    /// 全部单元结束后打印汇总表, 任一单元失败时返回错误
+    #[requires(self.assembled)]
+    pub async fn exec_matrix(
+        &self,
+        cells: Vec<MatrixCell>,
+        flows_name: Vec<String>,
+        opts: ExecOptions,
+        var_space: VarSpace,
+        parallel: bool,
+    ) -> RunResult<Vec<Job>> {
+        let main_ctx = ExecContext::new(opts.out(), opts.is_dryrun())
+            .with_plan(opts.is_plan())
+            .with_executor(opts.executor().clone())
+            .with_cache_dir(opts.cache_dir().cloned());
+        let mut jobs = Vec::new();
+        let mut failures = Vec::new();
+        if parallel {
+            let spc = Arc::new(self.clone());
+            let mut handles = Vec::new();
+            for cell in cells.iter().cloned() {
+                let spc = spc.clone();
+                let ctx = main_ctx.clone();
+                let flows = flows_name.clone();
+                let vars = var_space.clone();
+                handles.push(tokio::spawn(async move {
+                    spc.execute_cell(&ctx, &cell, &flows, vars).await
+                }));
+            }
+            for (cell, handle) in cells.iter().zip(handles) {
+                let (job, failed) = handle.await.unwrap_or_else(|e| {
+                    let mut job = Job::from(cell.name());
+                    let mut task = Task::from(cell.name());
+                    task.err(format!("panic: {e}"));
+                    job.append(task);
+                    (job, None)
+                });
+                jobs.push(job);
+                failures.push(failed.map(|x| (cell.envs().clone(), x)));
+            }
+        } else {
+            for cell in &cells {
+                let (job, failed) = self
+                    .execute_cell(&main_ctx, cell, &flows_name, var_space.clone())
+                    .await;
+                jobs.push(job);
+                failures.push(failed.map(|x| (cell.envs().clone(), x)));
+            }
+        }
+        // 全部单元结束后执行一次 hook, 失败时使用第一个失败单元的 env 与变量
+        let (envs, failure) = match failures.into_iter().flatten().next() {
+            Some((envs, failure)) => (envs, Some(failure)),
+            None => (
+                cells.first().map(|x| x.envs().clone()).unwrap_or_default(),
+                None,
+            ),
+        };
+        self.run_hooks(&main_ctx, &var_space, &envs, failure, None)
+            .await;
+        println!("\nmatrix summary:\n{}", matrix_summary(&jobs));
+        let failed: Vec<&str> = jobs
+            .iter()
+            .filter(|x| !x.is_suc())
+            .map(|x| x.name().as_str())
+            .collect();
+        if !failed.is_empty() {
+            return Err(
+                RunReason::Exec(format!("matrix cells failed: {}", failed.join(", "))).into(),
+            );
+        }
+        Ok(jobs)
+    }
+
+    // 单元内 flow 依次执行, 失败后不再执行后续 flow
+    async fn execute_cell(
+        &self,
+        main_ctx: &ExecContext,
+        cell: &MatrixCell,
+        flows_name: &[String],
+        mut var_space: VarSpace,
+    ) -> (Job, Option<FlowFailure>) {
+        println!("matrix cell: {}", cell.name());
+        cell.apply(&mut var_space);
+        let main_ctx = &main_ctx.clone().with_cache_scope(cell.name());
+        let mut job = Job::from(cell.name());
+        let mut failure = None;
+        for flow_name in flows_name {
+            match self
+                .execute_flow(
+                    main_ctx,
+                    &var_space,
+                    cell.envs(),
+                    flow_name,
+                    None,
+                    None,
+                    &mut failure,
+                )
+                .await
+            {
+                Ok(rec) => job.append(rec),
+                Err(e) => {
+                    error!(target: "exec", "matrix cell {} failed: {e}", cell.name());
+                    let mut task = Task::from(flow_name);
+                    task.err(e.to_string());
+                    job.append(task);
+                    break;
+                }
+            }
+        }
+        (job, failure)
     }
 
+    /// 失败时把失败信息写入 failure, 由调用方在全部 flow 结束后交给 hook
     #[requires(self.assembled)]
+    #[allow(clippy::too_many_arguments)]
     async fn execute_flow(
         &self,
         main_ctx: &ExecContext,
         var_space: &VarSpace,
         envs: &[String],
         flow_name: &str,
+        store: Option<&RunStore>,
         sender: Option<Sender<ReadSignal>>,
-    ) -> RunResult<()> {
+        failure: &mut Option<FlowFailure>,
+    ) -> RunResult<ExecOut> {
         let flow_name = self.normalize_flow_name(flow_name);
         println!("execute flow: {}", flow_name);
 
         let mut exec_sequ = ExecSequence::from("flow");
+        if let Some(store) = store {
+            exec_sequ = exec_sequ
+                .with_state(store.flow_state(&flow_name).err_conv()?)
+                .with_journal(store.undo_journal().err_conv()?);
+        }
         let mut ctx = main_ctx.clone();
 
         self.load_envs(&mut ctx, envs, &mut exec_sequ)?;
@@ -246,22 +460,295 @@ impl GxlSpace {
         self.load_flow(flow_ctx, &mut exec_sequ, &flow_name)
             .err_conv()?;
 
-        let exec_ctx = main_ctx.clone().with_subcontext("exec");
+        let exec_ctx = main_ctx
+            .clone()
+            .with_subcontext("exec")
+            .with_cache_scope(&format!("env={}", envs.join(",")));
 
-        match exec_sequ
-            .execute(exec_ctx, var_space.clone(), self, sender)
-            .await
-            .err_conv()
-        {
+        let result = exec_sequ
+            .execute_traced(exec_ctx, var_space.clone(), self, sender)
+            .await;
+        match result {
             Ok(TaskValue { rec, .. }) => {
-                task_local_report(rec);
-                Ok(())
+                if !*main_ctx.plan() {
+                    task_local_report(rec.clone());
+                }
+                Ok(rec)
+            }
+            Err((do_err, flow, vars)) => {
+                failure.get_or_insert(FlowFailure {
+                    flow,
+                    err: do_err.to_string(),
+                    vars,
+                });
+                Err::<ExecOut, _>(do_err).err_conv()
             }
-            Err(do_err) => {
-                //todo report;
-                Err(do_err)
+        }
+    }
+
+    /// 运行 #[on_failure](仅失败时) 与 #[always] flow, 失败时使用失败序列的变量;
+    /// 每个 hook 独立执行, 自身的错误只记录, 不影响主流程结果
+    async fn run_hooks(
+        &self,
+        main_ctx: &ExecContext,
+        var_space: &VarSpace,
+        envs: &[String],
+        failure: Option<FlowFailure>,
+        sender: Option<Sender<ReadSignal>>,
+    ) {
+        let mut hooks = Vec::new();
+        if failure.is_some() {
+            for mox in self.mods.values() {
+                hooks.extend(mox.fail_hooks().iter());
             }
         }
+        for mox in self.mods.values() {
+            hooks.extend(mox.always_hooks().iter());
+        }
+        if hooks.is_empty() {
+            return;
+        }
+        let vars = match failure {
+            Some(FlowFailure {
+                flow,
+                err,
+                mut vars,
+            }) => {
+                vars.global_mut().set(gxl_const::FAIL_FLOW, flow.as_str());
+                vars.global_mut().set(gxl_const::FAIL_ERR, err.as_str());
+                vars.global_mut().set(gxl_const::RUN_STATUS, "fail");
+                vars
+            }
+            None => {
+                let mut vars = var_space.clone();
+                vars.global_mut().set(gxl_const::RUN_STATUS, "suc");
+                vars
+            }
+        };
+        let ctx = main_ctx.clone().with_subcontext("hook");
+        let mut job = Job::from("hook");
+        for hook in hooks {
+            println!("hook flow: {}", hook.long_name());
+            let mut env_ctx = main_ctx.clone();
+            let mut hook_sequ = ExecSequence::from("hook");
+            let result = match self
+                .load_envs(&mut env_ctx, envs, &mut hook_sequ)
+                .and_then(|_| {
+                    self.guard_load_flow(hook, &RunUnitGuard::from_hook(hook), &mut hook_sequ)
+                        .err_conv()
+                }) {
+                Ok(_) => hook_sequ
+                    .execute(ctx.clone(), vars.clone(), self, sender.clone())
+                    .await
+                    .err_conv(),
+                Err(e) => Err(e),
+            };
+            let mut task = Task::from(format!("hook:{}", hook.long_name()));
+            match result {
+                Ok(TaskValue { rec, .. }) => {
+                    task.append(rec);
+                    task.finish();
+                }
+                Err(e) => {
+                    error!(target: ctx.path(), "hook failed for {}: {e}", hook.long_name());
+                    task.err(e.to_string());
+                }
+            }
+            job.append(task);
+        }
+        if !*main_ctx.plan() {
+            task_local_report(ExecOut::Job(job));
+        }
+    }
+
+    /// 按登记的逆序执行日志中未完成的 undo flow, 结果作为 rollback Job 上报;
+    /// 日志不含密文变量, 先执行 envs 重新加载
+    #[requires(self.assembled)]
+    pub async fn rollback(
+        &self,
+        store: &RunStore,
+        envs: &[String],
+        out: Option<bool>,
+        var_space: VarSpace,
+    ) -> RunResult<()> {
+        let mut journal = store.undo_journal().err_conv()?;
+        println!(
+            "rollback run: {} ({} pending)",
+            store.run_id(),
+            journal.entries().len()
+        );
+        let ctx = ExecContext::new(out, false).with_subcontext("rollback");
+        let var_space = self.load_vars(&ctx, envs, var_space).await?;
+        let mut job = Job::from("rollback");
+        let mut aborted = false;
+        // 失败的 undo 放回日志, 便于再次 rollback 时重试
+        let mut failed = Vec::new();
+        while let Some(entry) = journal.pop().err_conv()? {
+            println!("undo flow: {}", entry.flow());
+            let mut vars = var_space.clone();
+            entry.restore(&mut vars);
+            let mut undo_sequ = ExecSequence::from("undo");
+            let result = match self.load_flow(ctx.clone(), &mut undo_sequ, entry.flow()) {
+                Ok(_) => undo_sequ.execute(ctx.clone(), vars, self, None).await,
+                Err(e) => Err(e),
+            };
+            let mut task = Task::from(format!("undo:{}", entry.flow()));
+            match result {
+                Ok(TaskValue { rec, .. }) => {
+                    task.append(rec);
+                    task.finish();
+                }
+                Err(e) => {
+                    error!(target: ctx.path(), "Undo failed for {}: {e}", entry.flow());
+                    task.err(e.to_string());
+                    aborted = *entry.policy() == UndoFailPolicy::Abort;
+                    failed.push(entry);
+                }
+            }
+            job.append(task);
+            if aborted {
+                // 按登记时的 on_undo_fail="abort" 放弃剩余 undo, 剩余 undo 保留在日志中
+                warn!(target: ctx.path(), "rollback aborted, skip {} undo", journal.entries().len());
+                for skip in journal.entries().iter().rev() {
+                    let mut task = Task::from(format!("undo:{}", skip.flow()));
+                    task.err("skipped: rollback aborted".to_string());
+                    job.append(task);
+                }
+                break;
+            }
+        }
+        failed.reverse();
+        journal.restore(failed).err_conv()?;
+        let suc = job.is_suc();
+        report_rollback(job).await;
+        if !suc {
+            return Err(RunReason::Exec(format!(
+                "rollback {} failed, {} undo pending",
+                store.run_id(),
+                journal.entries().len()
+            ))
+            .into());
+        }
+        Ok(())
+    }
+
+    /// 执行指定 env 与 main mod 的属性, 返回得到的变量空间, 供 repl 与 rollback 使用
+    #[requires(self.assembled)]
+    pub async fn load_vars(
+        &self,
+        ctx: &ExecContext,
+        envs: &[String],
+        var_space: VarSpace,
+    ) -> RunResult<VarSpace> {
+        let mut sequ = ExecSequence::from("vars");
+        self.load_envs(&mut ctx.clone(), envs, &mut sequ)?;
+        let mox = self.main().err_conv()?;
+        sequ.append_hold(&RunUnitGuard::from_mod(mox.meta()), mox.props().clone());
+        let TaskValue { vars, .. } = sequ
+            .execute(ctx.clone(), var_space, self, None)
+            .await
+            .err_conv()?;
+        Ok(vars)
+    }
+
+    /// #[test] flow 的全名及是否 #[should_fail]; filter 按全名子串过滤
+    pub fn test_flows(&self, filter: Option<&str>) -> Vec<(String, bool)> {
+        let mut flows = Vec::new();
+        for mox in self.mods.values() {
+            for flow in mox.flows().values().filter(|x| x.is_test()) {
+                let name = flow.meta().long_name();
+                if filter.is_none_or(|x| name.contains(x)) {
+                    flows.push((name, flow.is_should_fail()));
+                }
+            }
+        }
+        flows
+    }
+
+    /// gflow test: 依次运行 #[test] flow, 每个 flow 使用独立的 VarSpace, 不触发 hook
+    #[requires(self.assembled)]
+    pub async fn run_tests(
+        &self,
+        envs: &[String],
+        filter: Option<&str>,
+        opts: ExecOptions,
+        var_space: VarSpace,
+    ) -> RunResult<TestReport> {
+        let main_ctx = ExecContext::new(opts.out(), false)
+            .with_executor(opts.executor().clone())
+            .with_cache_dir(opts.cache_dir().cloned());
+        let flows = self.test_flows(filter);
+        println!("running {} tests", flows.len());
+        let mut report = TestReport::default();
+        for (flow_name, should_fail) in flows {
+            let begin = Instant::now();
+            let mut job = Job::from(&flow_name);
+            let error = match self
+                .execute_test(&main_ctx, envs, &flow_name, var_space.clone())
+                .await
+            {
+                Ok(rec) => {
+                    job.append(rec);
+                    None
+                }
+                Err(e) => {
+                    let mut task = Task::from(&flow_name);
+                    task.err(e.to_string());
+                    job.append(task);
+                    Some(e.to_string())
+                }
+            };
+            let case = TestCase::new(job, should_fail, error, begin.elapsed());
+            println!("{}", case.line());
+            report.append(case);
+        }
+        println!("\n{}", report.summary());
+        Ok(report)
+    }
+
+    async fn execute_test(
+        &self,
+        main_ctx: &ExecContext,
+        envs: &[String],
+        flow_name: &str,
+        var_space: VarSpace,
+    ) -> RunResult<ExecOut> {
+        let mut sequ = ExecSequence::from("test");
+        self.load_envs(&mut main_ctx.clone(), envs, &mut sequ)?;
+        self.load_flow(main_ctx.clone(), &mut sequ, flow_name)
+            .err_conv()?;
+        let ctx = main_ctx.clone().with_subcontext("test");
+        let TaskValue { rec, .. } = sequ.execute(ctx, var_space, self, None).await.err_conv()?;
+        Ok(rec)
+    }
+
+    /// flow 关系图; 指定 flow 时只保留其执行序列涉及的 flow
+    #[requires(self.assembled)]
+    pub fn graph(&self, flow: Option<&str>) -> ExecResult<FlowGraph> {
+        let mut graph = FlowGraph::build(self);
+        if let Some(flow) = flow {
+            let mut sequ = ExecSequence::from("graph");
+            let flow_name = self.normalize_flow_name(flow);
+            self.load_flow(ExecContext::default(), &mut sequ, &flow_name)?;
+            let metas: Vec<FlowMeta> = sequ
+                .run_items()
+                .iter()
+                .filter_map(|x| match x.gxl_meta() {
+                    GxlMeta::Flow(meta) => Some(meta),
+                    _ => None,
+                })
+                .collect();
+            graph.retain_flows(&metas);
+        }
+        Ok(graph)
+    }
+
+    /// 是否存在该 flow, 未指定 mod 时在 main 中查找
+    pub fn has_flow(&self, name: &str) -> bool {
+        let full = self.normalize_flow_name(name);
+        full.split_once('.')
+            .and_then(|(m, f)| self.get(m)?.load_scope_flow(f))
+            .is_some()
     }
 
     fn normalize_flow_name(&self, name: &str) -> String {
@@ -440,6 +927,9 @@ mod tests {
         debug!("Job result: {task_v:#?}",);
 
         work_space.show().unwrap();
+        assert!(work_space.has_flow("flow1"));
+        assert!(work_space.has_flow("main.flow1"));
+        assert!(!work_space.has_flow("test"));
         Ok(())
     }
 }
@@ -0,0 +1,196 @@
+use std::sync::mpsc::Sender;
+
+use super::prelude::*;
+use crate::{
+    ability::prelude::TaskValue,
+    execution::{runnable::AsyncRunnableWithSenderTrait, task::Task},
+    sec::{SecFrom, SecValueObj, SecValueType, ToUniCase},
+    traits::Setter,
+    util::redirect::ReadSignal,
+};
+
+use super::gxl_block::BlockNode;
+
+/// try { ... } catch (err) { ... } finally { ... }
+/// 捕获 try 块的错误, 错误信息以 ${ERR.REASON}, ${ERR.MSG}, ${ERR.CODE} 暴露给 catch 块;
+/// 中断不会被捕获; finally 总会执行, 其错误优先于前面的结果
+#[derive(Clone, Getters)]
+pub struct GxlTry {
+    try_block: BlockNode,
+    catch_var: Option<String>,
+    catch_block: Option<BlockNode>,
+    finally_block: Option<BlockNode>,
+}
+
+impl GxlTry {
+    pub fn new(try_block: BlockNode) -> Self {
+        Self {
+            try_block,
+            catch_var: None,
+            catch_block: None,
+            finally_block: None,
+        }
+    }
+    pub fn with_catch(mut self, var: Option<String>, block: BlockNode) -> Self {
+        self.catch_var = var;
+        self.catch_block = Some(block);
+        self
+    }
+    pub fn with_finally(mut self, block: Option<BlockNode>) -> Self {
+        self.finally_block = block;
+        self
+    }
+
+    async fn exec_catch(
+        &self,
+        ctx: ExecContext,
+        mut dict: VarSpace,
+        sender: Option<Sender<ReadSignal>>,
+        err: ExecError,
+        task: &mut Task,
+    ) -> TaskResult {
+        let Some(catch_block) = &self.catch_block else {
+            return Err(err);
+        };
+        if matches!(err.reason(), ExecReason::Interrupted(_)) {
+            return Err(err);
+        }
+        info!(target: ctx.path(), "caught: {err}");
+        task.stdout.push_str(&format!("caught: {err}\n"));
+        if let Some(var) = &self.catch_var {
+            dict.global_mut().set(var.clone(), error_value(&err));
+        }
+        catch_block.async_exec(ctx, dict, sender).await
+    }
+}
+
+pub fn reason_name(reason: &ExecReason) -> &'static str {
+    match reason {
+        ExecReason::OsCmd(..) => "cmd",
+        ExecReason::Io(_) => "io",
+        ExecReason::InvalidPath(_) => "path",
+        ExecReason::Check(_) => "check",
+        ExecReason::Args(_) => "args",
+        ExecReason::Depend(_) => "depend",
+        ExecReason::Exp(_) => "exp",
+        ExecReason::Bug(_) => "bug",
+        ExecReason::NoVal(_) => "no_val",
+        ExecReason::Miss(_) => "miss",
+        ExecReason::Uvs(_) => "uvs",
+        ExecReason::NetWork(_) => "network",
+        ExecReason::Timeout(..) => "timeout",
+        ExecReason::Interrupted(_) => "interrupted",
+    }
+}
+
+fn error_value(err: &ExecError) -> SecValueType {
+    let code = match err.reason() {
+        ExecReason::OsCmd(_, code, _) => *code as u64,
+        _ => 0,
+    };
+    let mut obj = SecValueObj::new();
+    obj.insert(
+        "reason".to_unicase(),
+        SecValueType::nor_from(reason_name(err.reason()).to_string()),
+    );
+    obj.insert(
+        "msg".to_unicase(),
+        SecValueType::nor_from(err.reason().to_string()),
+    );
+    obj.insert("code".to_unicase(), SecValueType::nor_from(code));
+    SecValueType::Obj(obj)
+}
+
+#[async_trait]
+impl AsyncRunnableWithSenderTrait for GxlTry {
+    async fn async_exec(
+        &self,
+        ctx: ExecContext,
+        dict: VarSpace,
+        sender: Option<Sender<ReadSignal>>,
+    ) -> TaskResult {
+        let mut task = Task::from("try");
+        let result = match self
+            .try_block
+            .async_exec(ctx.clone(), dict.clone(), sender.clone())
+            .await
+        {
+            Ok(value) => Ok(value),
+            Err(e) => {
+                self.exec_catch(ctx.clone(), dict.clone(), sender.clone(), e, &mut task)
+                    .await
+            }
+        };
+        let Some(finally_block) = &self.finally_block else {
+            return result.map(|value| finish_value(task, value));
+        };
+        let (cur_dict, outcome) = match result {
+            Ok(value) => (value.vars.clone(), Ok(value)),
+            Err(e) => (dict, Err(e)),
+        };
+        let TaskValue { vars, rec, .. } = finally_block.async_exec(ctx, cur_dict, sender).await?;
+        let value = outcome?;
+        task.append(rec);
+        Ok(finish_value(
+            task,
+            TaskValue::from((vars, value.rec)).with_ctrl(value.ctrl),
+        ))
+    }
+}
+
+fn finish_value(mut task: Task, value: TaskValue) -> TaskValue {
+    let TaskValue { vars, rec, ctrl } = value;
+    task.append(rec);
+    task.finish();
+    TaskValue::from((vars, ExecOut::Task(task))).with_ctrl(ctrl)
+}
+
+#[cfg(test)]
+mod tests {
+    use orion_error::TestAssert;
+
+    use super::*;
+    use crate::{
+        ability::{GxCmd, GxEcho},
+        components::gxl_block::BlockAction,
+    };
+
+    fn cmd_block(cmd: &str) -> BlockNode {
+        let mut block = BlockNode::new();
+        block.append(BlockAction::from(GxCmd::new(cmd.to_string())));
+        block
+    }
+
+    #[tokio::test]
+    async fn test_try_catch_finally() {
+        let ctx = ExecContext::default();
+        let mut catch_block = BlockNode::new();
+        catch_block.append(BlockAction::from(GxEcho::new("${ERR.REASON}: ${ERR.MSG}")));
+        let node = GxlTry::new(cmd_block("exit 3"))
+            .with_catch(Some("err".into()), catch_block)
+            .with_finally(Some(cmd_block("true")));
+        let TaskValue { vars, .. } = node
+            .async_exec(ctx.clone(), VarSpace::default(), None)
+            .await
+            .assert();
+        assert_eq!(
+            vars.get("err.reason"),
+            Some(SecValueType::nor_from("cmd".to_string()))
+        );
+        assert_eq!(vars.get("err.code"), Some(SecValueType::nor_from(3u64)));
+
+        // 无 catch 时错误在 finally 之后继续抛出
+        let node = GxlTry::new(cmd_block("exit 1")).with_finally(Some(cmd_block("true")));
+        assert!(node
+            .async_exec(ctx.clone(), VarSpace::default(), None)
+            .await
+            .is_err());
+
+        // finally 的错误优先
+        let node = GxlTry::new(cmd_block("true")).with_finally(Some(cmd_block("exit 2")));
+        assert!(node
+            .async_exec(ctx, VarSpace::default(), None)
+            .await
+            .is_err());
+    }
+}
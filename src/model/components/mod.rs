@@ -7,10 +7,12 @@ pub mod gxl_flow;
 //pub mod gxl_intercept;
 pub mod gxl_act;
 pub mod gxl_fun;
+pub mod gxl_graph;
 pub mod gxl_loop;
 pub mod gxl_mod;
 pub mod gxl_prop;
 pub mod gxl_spc;
+pub mod gxl_try;
 pub mod gxl_utls;
 pub mod gxl_var;
 pub mod prelude;
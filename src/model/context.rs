@@ -1,8 +1,12 @@
 use std::collections::HashMap;
 use std::env;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
 use orion_common::friendly::AppendAble;
 
+use crate::util::executor::SharedExecutor;
+
 #[derive(Debug, Clone, Default, Getters)]
 pub struct ExecContext {
     env_vars: HashMap<String, String>,
@@ -11,6 +15,17 @@ pub struct ExecContext {
     #[getter(copy)]
     quiet: Option<bool>,
     dryrun: bool,
+    // --plan: 只打印将要执行的命令
+    plan: bool,
+    //上层 flow 的截止时间
+    #[getter(copy)]
+    deadline: Option<Instant>,
+    // 外部命令的执行器: 直接运行, --record 或 --replay
+    executor: SharedExecutor,
+    // 增量执行指纹的目录, 未设置时为 _gal/.cache
+    cache_dir: Option<PathBuf>,
+    // 选用的 env 与矩阵单元, 计入增量执行的指纹
+    cache_scope: String,
     //accessor: Rc<UniversalAccessor>,
 }
 impl ExecContext {
@@ -34,10 +49,49 @@ impl ExecContext {
         format!("{}:{}", tag, self.abs_path)
     }
 
+    pub fn with_plan(mut self, plan: bool) -> Self {
+        self.plan = plan;
+        self
+    }
+
+    pub fn with_executor(mut self, executor: SharedExecutor) -> Self {
+        self.executor = executor;
+        self
+    }
+
+    pub fn with_cache_dir(mut self, cache_dir: Option<PathBuf>) -> Self {
+        self.cache_dir = cache_dir;
+        self
+    }
+
+    pub fn with_cache_scope(mut self, scope: &str) -> Self {
+        if self.cache_scope.is_empty() {
+            self.cache_scope = scope.to_string();
+        } else {
+            self.cache_scope = format!("{}/{scope}", self.cache_scope);
+        }
+        self
+    }
+
     pub fn with_subcontext(mut self, arg: &str) -> Self {
         self.append(arg);
         self
     }
+    /// 收紧截止时间; 已有更早的截止时间时保持不变
+    pub fn limit_deadline(&mut self, timeout: Duration) {
+        let deadline = Instant::now() + timeout;
+        self.deadline = Some(self.deadline.map_or(deadline, |x| x.min(deadline)));
+    }
+    /// 命令超时取自身设置与剩余时间中较小者
+    pub fn cmd_timeout(&self, local: Option<Duration>) -> Option<Duration> {
+        let remain = self
+            .deadline
+            .map(|x| x.saturating_duration_since(Instant::now()));
+        match (local, remain) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
 }
 impl AppendAble<&str> for ExecContext {
     fn append(&mut self, now: &str) {
@@ -60,3 +114,26 @@ impl AppendAble<String> for ExecContext {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cmd_timeout() {
+        let mut ctx = ExecContext::default();
+        assert_eq!(ctx.cmd_timeout(None), None);
+        assert_eq!(
+            ctx.cmd_timeout(Some(Duration::from_secs(5))),
+            Some(Duration::from_secs(5))
+        );
+        ctx.limit_deadline(Duration::from_secs(60));
+        ctx.limit_deadline(Duration::from_secs(600));
+        let remain = ctx.cmd_timeout(None).unwrap();
+        assert!(remain <= Duration::from_secs(60));
+        assert_eq!(
+            ctx.cmd_timeout(Some(Duration::from_secs(5))),
+            Some(Duration::from_secs(5))
+        );
+    }
+}
@@ -47,6 +47,10 @@ pub enum ExecReason {
     Uvs(UvsReason),
     #[error("{0}")]
     NetWork(String),
+    #[error("timeout : {0} ({1})")]
+    Timeout(String, String),
+    #[error("interrupted : {0}")]
+    Interrupted(String),
 }
 impl From<UvsReason> for ExecReason {
     fn from(value: UvsReason) -> Self {
@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use walkdir::WalkDir;
+use wildmatch::WildMatch;
+
+use crate::{ExecReason, ExecResult};
+
+/// 增量执行指纹的保存目录, 相对 gxl 配置文件所在目录
+pub const CACHE_DIR: &str = ".cache";
+
+/// #[inputs(...)] #[outputs(...)] 声明的增量执行条件;
+/// 路径相对当前目录, 支持 `*`, `?` 与 `**/`
+#[derive(Clone, Debug, PartialEq, Default, Getters)]
+pub struct FlowCache {
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    // flow 定义与求值后的变量, 变化时同样需要重新执行
+    context: String,
+}
+
+impl FlowCache {
+    pub fn new(inputs: Vec<String>, outputs: Vec<String>) -> Self {
+        Self {
+            inputs,
+            outputs,
+            context: String::new(),
+        }
+    }
+    pub fn with_context(mut self, context: String) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// 未指定目录时使用当前目录下的 _gal/.cache
+    pub fn cache_root(dir: Option<&Path>) -> PathBuf {
+        dir.map(Path::to_path_buf)
+            .unwrap_or_else(|| Path::new("_gal").join(CACHE_DIR))
+    }
+
+    /// 按 _1, _2 ... 的顺序取出位置参数
+    pub fn positional_args(args: &HashMap<String, String>) -> Vec<String> {
+        let mut items: Vec<(usize, &String)> = args
+            .iter()
+            .filter_map(|(k, v)| Some((k.strip_prefix('_')?.parse().ok()?, v)))
+            .collect();
+        items.sort_by_key(|x| x.0);
+        items.into_iter().map(|x| x.1.clone()).collect()
+    }
+
+    /// 上下文与输入输出文件的指纹: 文件按路径排序后依次计算 路径 + 内容;
+    /// 不存在的普通路径也计入, 以便文件被创建后能感知变化
+    pub fn fingerprint(&self) -> ExecResult<String> {
+        let mut hash = Fnv::default();
+        hash.write(b"ctx");
+        hash.write(&(self.context.len() as u64).to_le_bytes());
+        hash.write(self.context.as_bytes());
+        for (tag, patterns) in [("in", &self.inputs), ("out", &self.outputs)] {
+            hash.write(tag.as_bytes());
+            for pattern in patterns {
+                let files = expand_pattern(pattern);
+                if files.is_empty() {
+                    hash.write(format!("missing:{pattern}").as_bytes());
+                }
+                for file in files {
+                    let data = fs::read(&file)
+                        .map_err(|e| ExecReason::Io(format!("{}: {e}", file.display())))?;
+                    hash.write(file.to_string_lossy().as_bytes());
+                    hash.write(&(data.len() as u64).to_le_bytes());
+                    hash.write(&data);
+                }
+            }
+        }
+        Ok(format!("{:016x}", hash.finish()))
+    }
+
+    /// 所有输出都存在且指纹与上次成功执行时一致
+    pub fn is_up_to_date(&self, root: &Path, key: &str) -> bool {
+        if self.outputs.iter().any(|x| expand_pattern(x).is_empty()) {
+            return false;
+        }
+        let Ok(saved) = fs::read_to_string(root.join(key)) else {
+            return false;
+        };
+        self.fingerprint().is_ok_and(|x| x == saved.trim())
+    }
+
+    /// 成功执行后记录指纹
+    pub fn save(&self, root: &Path, key: &str) -> ExecResult<()> {
+        let path = root.join(key);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| ExecReason::Io(e.to_string()))?;
+        }
+        fs::write(&path, self.fingerprint()?).map_err(|e| ExecReason::Io(e.to_string()))?;
+        Ok(())
+    }
+}
+
+/// 文本中引用的变量名 `${NAME}`, 去重并排序
+pub fn var_refs(text: &str) -> Vec<String> {
+    let mut names: Vec<String> = text
+        .split("${")
+        .skip(1)
+        .filter_map(|x| x.split_once('}'))
+        .map(|(name, _)| name.trim().to_uppercase())
+        .filter(|x| !x.is_empty())
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+fn has_wildcard(text: &str) -> bool {
+    text.contains(['*', '?'])
+}
+
+/// 展开路径模式, 目录会展开为其下的全部文件, 结果按路径排序
+fn expand_pattern(pattern: &str) -> Vec<PathBuf> {
+    let pattern = pattern.trim_start_matches("./");
+    if !has_wildcard(pattern) {
+        return walk_files(Path::new(pattern));
+    }
+    // 通配符之前的目录作为遍历起点
+    let base: PathBuf = Path::new(pattern)
+        .components()
+        .take_while(|x| !has_wildcard(&x.as_os_str().to_string_lossy()))
+        .collect();
+    let root = if base.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        base
+    };
+    walk_files(&root)
+        .into_iter()
+        .filter(|x| glob_match(pattern, &x.to_string_lossy()))
+        .collect()
+}
+
+fn walk_files(root: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = WalkDir::new(root)
+        .into_iter()
+        .filter_map(|x| x.ok())
+        .filter(|x| x.file_type().is_file())
+        .map(|x| {
+            let path = x.into_path();
+            path.strip_prefix("./")
+                .map(Path::to_path_buf)
+                .unwrap_or(path)
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+// `**/` 可匹配零层目录: src/**/*.rs 同时匹配 src/main.rs
+fn glob_match(pattern: &str, path: &str) -> bool {
+    WildMatch::new(pattern).matches(path)
+        || (pattern.contains("**/") && WildMatch::new(&pattern.replace("**/", "")).matches(path))
+}
+
+// FNV-1a, 结果不随版本变化, 可安全落盘
+struct Fnv(u64);
+
+impl Default for Fnv {
+    fn default() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+}
+
+impl Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("src/**/*.rs", "src/main.rs"));
+        assert!(glob_match("src/**/*.rs", "src/model/mod.rs"));
+        assert!(!glob_match("src/**/*.rs", "tests/main.rs"));
+        assert!(glob_match("Cargo.*", "Cargo.lock"));
+    }
+
+    #[test]
+    fn test_var_refs() {
+        let text = r#"gx.cmd ("cp ${SRC} ${dst}/${Src}"); X = "${ERR.CODE}";"#;
+        assert_eq!(var_refs(text), vec!["DST", "ERR.CODE", "SRC"]);
+        assert!(var_refs("echo $HOME").is_empty());
+    }
+
+    #[test]
+    fn test_positional_args() {
+        let args = HashMap::from([
+            ("_2".to_string(), "b".to_string()),
+            ("_1".to_string(), "a".to_string()),
+            ("name".to_string(), "x".to_string()),
+        ]);
+        assert_eq!(FlowCache::positional_args(&args), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let cache = FlowCache::new(vec!["src/util/*.sh".into()], vec!["Cargo.toml".into()]);
+        let first = cache.fingerprint().unwrap();
+        assert_eq!(first, cache.fingerprint().unwrap());
+        let other = FlowCache::new(vec!["src/util/*.sh".into()], vec!["no_such_file".into()]);
+        assert_ne!(first, other.fingerprint().unwrap());
+        assert!(!other.is_up_to_date(&FlowCache::cache_root(None), "test.no_such_flow"));
+        // flow 定义或变量变化后指纹随之变化
+        let changed = cache.clone().with_context("MODE=release".into());
+        assert_ne!(first, changed.fingerprint().unwrap());
+    }
+
+    #[test]
+    fn test_save_under_root() {
+        let root = std::env::temp_dir().join(format!("gxl_cache_{}", std::process::id()));
+        let cache = FlowCache::new(vec!["Cargo.toml".into()], vec!["Cargo.toml".into()])
+            .with_context("MODE=debug".into());
+        cache.save(&root, "main.build").unwrap();
+        assert!(root.join("main.build").exists());
+        assert!(cache.is_up_to_date(&root, "main.build"));
+        let other = cache.clone().with_context("MODE=release".into());
+        assert!(!other.is_up_to_date(&root, "main.build"));
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -57,6 +57,10 @@ impl VarSpace {
     pub fn global_mut(&mut self) -> &mut VarDict {
         &mut self.global
     }
+    /// 并行分支结束后回写：只合并分支相对 base 的变更，按调用顺序后者覆盖前者
+    pub fn merge_branch(&mut self, base: &VarSpace, branch: VarSpace) {
+        self.global.merge_changes(&base.global, branch.global);
+    }
     pub fn get(&self, path: &str) -> Option<SecValueType> {
         self.global().maps().value_get(path)
     }
@@ -280,6 +284,40 @@ mod tests {
         assert!(var_space.get("parent.invalid").is_none());
     }
 
+    #[test]
+    fn test_merge_branch() {
+        let mut base = VarSpace::default();
+        base.global_mut().set("keep", "base");
+        base.global_mut().set("shared", "base");
+
+        let mut branch_a = base.clone();
+        branch_a.global_mut().set("a", "1");
+        branch_a.global_mut().set("shared", "a");
+        let mut branch_b = base.clone();
+        branch_b.global_mut().set("b", "2");
+        branch_b.global_mut().set("shared", "b");
+
+        let mut target = base.clone();
+        target.merge_branch(&base, branch_a);
+        target.merge_branch(&base, branch_b);
+        assert_eq!(
+            target.get("keep"),
+            Some(SecValueType::nor_from("base".to_string()))
+        );
+        assert_eq!(
+            target.get("a"),
+            Some(SecValueType::nor_from("1".to_string()))
+        );
+        assert_eq!(
+            target.get("b"),
+            Some(SecValueType::nor_from("2".to_string()))
+        );
+        assert_eq!(
+            target.get("shared"),
+            Some(SecValueType::nor_from("b".to_string()))
+        );
+    }
+
     #[test]
     fn test_get_with_empty_path() {
         let mut var_space = VarSpace::default();
@@ -4,7 +4,7 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use derive_more::From;
 
-use crate::annotation::{Dryrunable, Transaction};
+use crate::annotation::{Dryrunable, Parallelable, Transaction};
 use crate::components::gxl_flow::meta::FlowMetaHold;
 use crate::components::gxl_spc::GxlSpace;
 use crate::components::{GxlEnv, GxlFlow, GxlMod, GxlProps};
@@ -15,7 +15,9 @@ use crate::traits::DependTrait;
 use crate::util::redirect::ReadSignal;
 
 use super::runnable::{AsyncRunnableTrait, ComponentMeta, TaskResult, TaskValue};
+use super::trans::UndoFailPolicy;
 use super::VarSpace;
+use crate::ExecResult;
 #[derive(Clone, From)]
 pub enum AsyncComHold {
     #[from(GxlFlow)]
@@ -88,6 +90,15 @@ impl Transaction for AsyncComHold {
             AsyncComHold::Env(_) | AsyncComHold::Props(_) | AsyncComHold::Mox(_) => None,
         }
     }
+
+    fn undo_fail_policy(&self) -> ExecResult<UndoFailPolicy> {
+        match self {
+            AsyncComHold::Flow(h) => h.undo_fail_policy(),
+            AsyncComHold::Env(_) | AsyncComHold::Props(_) | AsyncComHold::Mox(_) => {
+                Ok(UndoFailPolicy::default())
+            }
+        }
+    }
 }
 #[derive(Clone, From, Getters)]
 pub struct IsolationHold {
@@ -99,6 +110,11 @@ pub enum ComHold {
     Conduction(AsyncComHold),
     Isolation(IsolationHold),
 }
+impl ComHold {
+    pub fn is_flow(&self) -> bool {
+        matches!(self.gxl_meta(), GxlMeta::Flow(_))
+    }
+}
 impl Transaction for ComHold {
     fn is_transaction(&self) -> bool {
         match self {
@@ -113,6 +129,13 @@ impl Transaction for ComHold {
             ComHold::Isolation(h) => h.undo_hold(),
         }
     }
+
+    fn undo_fail_policy(&self) -> ExecResult<UndoFailPolicy> {
+        match self {
+            ComHold::Conduction(h) => h.undo_fail_policy(),
+            ComHold::Isolation(h) => h.undo_fail_policy(),
+        }
+    }
 }
 impl Dryrunable for ComHold {
     fn dryrun_hold(&self) -> Option<FlowMetaHold> {
@@ -130,6 +153,10 @@ impl Transaction for IsolationHold {
     fn undo_hold(&self) -> Option<FlowMetaHold> {
         self.hold.undo_hold()
     }
+
+    fn undo_fail_policy(&self) -> ExecResult<UndoFailPolicy> {
+        self.hold.undo_fail_policy()
+    }
 }
 impl Dryrunable for IsolationHold {
     fn dryrun_hold(&self) -> Option<FlowMetaHold> {
@@ -137,6 +164,28 @@ impl Dryrunable for IsolationHold {
     }
 }
 
+impl Parallelable for AsyncComHold {
+    fn is_parallel(&self) -> bool {
+        match self {
+            AsyncComHold::Flow(h) => h.is_parallel(),
+            AsyncComHold::Env(_) | AsyncComHold::Props(_) | AsyncComHold::Mox(_) => false,
+        }
+    }
+}
+impl Parallelable for IsolationHold {
+    fn is_parallel(&self) -> bool {
+        self.hold.is_parallel()
+    }
+}
+impl Parallelable for ComHold {
+    fn is_parallel(&self) -> bool {
+        match self {
+            ComHold::Conduction(h) => h.is_parallel(),
+            ComHold::Isolation(h) => h.is_parallel(),
+        }
+    }
+}
+
 impl Dryrunable for AsyncComHold {
     fn dryrun_hold(&self) -> Option<FlowMetaHold> {
         match self {
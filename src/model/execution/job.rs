@@ -16,6 +16,13 @@ impl From<&str> for Job {
     }
 }
 
+impl Job {
+    /// 所有 task 都执行成功或被跳过
+    pub fn is_suc(&self) -> bool {
+        self.tasks.iter().all(|x| x.result.is_ok())
+    }
+}
+
 impl From<&String> for Job {
     fn from(name: &String) -> Self {
         Self {
@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, Write};
+#[cfg(unix)]
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use orion_error::ToStructError;
+
+use super::dict::galaxy_dot_path;
+use crate::annotation::FST_ARG_TAG;
+use crate::util::duration::parse_duration;
+use crate::util::signal::is_interrupted;
+use crate::{ExecReason, ExecResult};
+
+const RETRY_INTERVAL: Duration = Duration::from_millis(100);
+// 本进程已持有的可重入锁, gx.run 嵌套运行同一个工程时不再等待
+static HELD: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// 命名锁文件所在目录: ~/.galaxy/locks
+pub fn lock_dir() -> PathBuf {
+    galaxy_dot_path().join("locks")
+}
+
+/// #[lock("deploy-prod", timeout="10m")]: 跨进程互斥的命名锁, 未指定 timeout 时一直等待
+#[derive(Clone, Debug, PartialEq, Getters)]
+pub struct LockSpec {
+    name: String,
+    timeout: Option<Duration>,
+    reentrant: bool,
+}
+
+impl LockSpec {
+    pub fn new<S: Into<String>>(name: S, timeout: Option<Duration>) -> Self {
+        Self {
+            name: name.into(),
+            timeout,
+            reentrant: false,
+        }
+    }
+    /// 缺少锁名或 timeout 无法解析时报错, 不能退化为无锁执行
+    pub fn from_args(args: &HashMap<String, String>) -> ExecResult<Self> {
+        let name = args
+            .get("name")
+            .or(args.get(FST_ARG_TAG))
+            .map(|x| x.trim())
+            .unwrap_or_default();
+        if name.is_empty() {
+            return ExecReason::Args("lock name is empty".into()).err_result();
+        }
+        let timeout =
+            match args.get("timeout") {
+                Some(value) => Some(parse_duration(value).ok_or_else(|| {
+                    ExecReason::Args(format!("bad lock timeout: {value}")).to_err()
+                })?),
+                None => None,
+            };
+        Ok(Self::new(name, timeout))
+    }
+    /// 同一个 _gal 目录的工程锁, 避免两次 gflow 运行重叠
+    pub fn project(gal_dir: &Path, timeout: Option<Duration>) -> Self {
+        let path = fs::canonicalize(gal_dir).unwrap_or(gal_dir.to_path_buf());
+        Self {
+            reentrant: true,
+            ..Self::new(format!("project{}", path.display()), timeout)
+        }
+    }
+
+    /// 可重入锁已被本进程持有时返回 None
+    pub async fn acquire(&self) -> ExecResult<Option<FileLock>> {
+        FileLock::acquire(&lock_dir(), self).await
+    }
+}
+
+/// 文件锁(unix 下为 flock), drop 时关闭文件即释放
+#[derive(Debug)]
+pub struct FileLock {
+    file: File,
+    path: PathBuf,
+    reentrant: bool,
+}
+
+impl FileLock {
+    pub async fn acquire(dir: &Path, spec: &LockSpec) -> ExecResult<Option<Self>> {
+        fs::create_dir_all(dir).map_err(|e| ExecReason::Io(format!("{}: {e}", dir.display())))?;
+        let path = dir.join(format!("{}.lock", file_name(spec.name())));
+        if spec.reentrant && is_held(&path) {
+            return Ok(None);
+        }
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .map_err(|e| ExecReason::Io(format!("{}: {e}", path.display())))?;
+        let begin = Instant::now();
+        let mut waiting = false;
+        while !try_flock(&file)? {
+            if is_interrupted() {
+                return ExecReason::Interrupted(format!("wait lock {}", spec.name())).err_result();
+            }
+            if let Some(limit) = spec.timeout() {
+                if begin.elapsed() >= *limit {
+                    return ExecReason::Timeout(
+                        format!("lock {}", spec.name()),
+                        format!("{limit:?}"),
+                    )
+                    .err_result();
+                }
+            }
+            if !waiting {
+                waiting = true;
+                println!("wait lock {} (held by {})", spec.name(), holder(&file));
+            }
+            tokio::time::sleep(RETRY_INTERVAL).await;
+        }
+        if spec.reentrant {
+            if let Ok(mut held) = HELD.lock() {
+                held.push(path.clone());
+            }
+        }
+        let mut lock = Self {
+            file,
+            path,
+            reentrant: spec.reentrant,
+        };
+        lock.write_holder();
+        info!(target: "lock", "acquired lock {}", lock.path.display());
+        Ok(Some(lock))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // 记录持有者, 便于等待方提示
+    fn write_holder(&mut self) {
+        let _ = self.file.set_len(0);
+        let _ = self.file.rewind();
+        let _ = write!(self.file, "pid {}", std::process::id());
+        let _ = self.file.flush();
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        if self.reentrant {
+            if let Ok(mut held) = HELD.lock() {
+                held.retain(|x| x != &self.path);
+            }
+        }
+    }
+}
+
+fn is_held(path: &Path) -> bool {
+    HELD.lock().is_ok_and(|x| x.iter().any(|p| p == path))
+}
+
+#[cfg(unix)]
+fn try_flock(file: &File) -> ExecResult<bool> {
+    let ret = unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) };
+    if ret == 0 {
+        return Ok(true);
+    }
+    let err = std::io::Error::last_os_error();
+    if err.kind() == std::io::ErrorKind::WouldBlock {
+        return Ok(false);
+    }
+    Err(ExecReason::Io(format!("flock: {err}")).into())
+}
+
+// 非 unix 平台使用标准库的文件锁, 同样在文件关闭时释放
+#[cfg(not(unix))]
+fn try_flock(file: &File) -> ExecResult<bool> {
+    match file.try_lock() {
+        Ok(()) => Ok(true),
+        Err(std::fs::TryLockError::WouldBlock) => Ok(false),
+        Err(std::fs::TryLockError::Error(err)) => {
+            Err(ExecReason::Io(format!("lock file: {err}")).into())
+        }
+    }
+}
+
+fn holder(mut file: &File) -> String {
+    let mut text = String::new();
+    let _ = file.rewind();
+    let _ = file.read_to_string(&mut text);
+    if text.trim().is_empty() {
+        "unknown".into()
+    } else {
+        text.trim().to_string()
+    }
+}
+
+// 锁名中的路径分隔符等字符替换为 _
+fn file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_lock_spec() {
+        let args = HashMap::from([
+            (FST_ARG_TAG.to_string(), "deploy-prod".to_string()),
+            ("timeout".to_string(), "10m".to_string()),
+        ]);
+        let spec = LockSpec::from_args(&args).unwrap();
+        assert_eq!(spec.name(), "deploy-prod");
+        assert_eq!(spec.timeout(), &Some(Duration::from_secs(600)));
+        assert!(LockSpec::from_args(&HashMap::new()).is_err());
+        let args = HashMap::from([("name".to_string(), "db".to_string())]);
+        assert_eq!(
+            LockSpec::from_args(&args).unwrap(),
+            LockSpec::new("db", None)
+        );
+        let args = HashMap::from([
+            ("name".to_string(), "db".to_string()),
+            ("timeout".to_string(), "10 minutes".to_string()),
+        ]);
+        assert!(LockSpec::from_args(&args).is_err());
+        assert_eq!(file_name("project/home/a b/_gal"), "project_home_a_b__gal");
+    }
+
+    #[tokio::test]
+    async fn test_file_lock() {
+        let dir = TempDir::new().unwrap();
+        let spec = LockSpec::new("deploy-prod", Some(Duration::from_millis(300)));
+        let lock = FileLock::acquire(dir.path(), &spec).await.unwrap().unwrap();
+        assert_eq!(
+            fs::read_to_string(lock.path()).unwrap(),
+            format!("pid {}", std::process::id())
+        );
+
+        // flock 按打开的文件互斥, 同一进程内再次获取也会等待
+        let begin = Instant::now();
+        let err = FileLock::acquire(dir.path(), &spec).await.unwrap_err();
+        assert!(matches!(err.reason(), ExecReason::Timeout(..)));
+        assert!(begin.elapsed() >= Duration::from_millis(300));
+
+        drop(lock);
+        assert!(FileLock::acquire(dir.path(), &spec)
+            .await
+            .unwrap()
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_project_lock_reentrant() {
+        let dir = TempDir::new().unwrap();
+        let spec = LockSpec::project(dir.path(), Some(Duration::from_millis(100)));
+        let lock = FileLock::acquire(dir.path(), &spec).await.unwrap();
+        assert!(lock.is_some());
+        // 嵌套运行同一工程时直接通过
+        assert!(FileLock::acquire(dir.path(), &spec)
+            .await
+            .unwrap()
+            .is_none());
+        drop(lock);
+        let again = FileLock::acquire(dir.path(), &spec).await.unwrap();
+        assert!(again.is_some());
+    }
+}
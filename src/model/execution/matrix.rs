@@ -0,0 +1,135 @@
+use orion_error::ToStructError;
+
+use crate::execution::job::Job;
+use crate::execution::VarSpace;
+use crate::traits::Setter;
+use crate::{ExecReason, ExecResult};
+
+/// 矩阵执行的一个单元: 使用的 env 以及额外设置的矩阵变量
+#[derive(Clone, Debug, PartialEq, Getters)]
+pub struct MatrixCell {
+    name: String,
+    envs: Vec<String>,
+    var: Option<(String, String)>,
+}
+
+impl MatrixCell {
+    /// --matrix 不带参数时每个 env 一个单元;
+    /// --matrix KEY=v1,v2 时每个取值一个单元, 各单元使用全部 env
+    pub fn build(envs: &[String], spec: &str) -> ExecResult<Vec<Self>> {
+        let spec = spec.trim();
+        if spec.is_empty() {
+            return Ok(envs
+                .iter()
+                .map(|env| Self {
+                    name: env.clone(),
+                    envs: vec![env.clone()],
+                    var: None,
+                })
+                .collect());
+        }
+        let bad_spec =
+            || ExecReason::Args(format!("bad matrix: {spec}, eg: ARCH=x86,arm")).err_result();
+        let Some((key, values)) = spec.split_once('=') else {
+            return bad_spec();
+        };
+        let key = key.trim();
+        let cells: Vec<Self> = values
+            .split(',')
+            .map(str::trim)
+            .filter(|x| !x.is_empty())
+            .map(|value| Self {
+                name: format!("{key}={value}"),
+                envs: envs.to_vec(),
+                var: Some((key.to_string(), value.to_string())),
+            })
+            .collect();
+        if key.is_empty() || cells.is_empty() {
+            return bad_spec();
+        }
+        Ok(cells)
+    }
+
+    pub fn apply(&self, vars: &mut VarSpace) {
+        if let Some((key, value)) = &self.var {
+            vars.global_mut().set(key.as_str(), value.as_str());
+        }
+    }
+}
+
+/// 矩阵汇总表, 每个单元一行
+pub fn matrix_summary(jobs: &[Job]) -> String {
+    let width = jobs
+        .iter()
+        .map(|x| x.name().len())
+        .max()
+        .unwrap_or(0)
+        .max(4);
+    let mut out = format!(
+        "{:<width$}  {:<6}  {:>5}  {:>7}\n",
+        "cell", "status", "tasks", "skipped"
+    );
+    for job in jobs {
+        let status = if job.is_suc() { "suc" } else { "fail" };
+        let skipped = job.tasks().iter().filter(|x| x.is_skipped()).count();
+        out.push_str(&format!(
+            "{:<width$}  {:<6}  {:>5}  {:>7}",
+            job.name(),
+            status,
+            job.tasks().len(),
+            skipped
+        ));
+        if let Some(err) = job.tasks().iter().find_map(|x| x.result.as_ref().err()) {
+            out.push_str(&format!("  {}", err.lines().next().unwrap_or_default()));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use orion_common::friendly::AppendAble;
+
+    use crate::execution::task::Task;
+
+    use super::*;
+
+    #[test]
+    fn test_matrix_build() {
+        let envs = vec!["dev".to_string(), "release".to_string()];
+        let cells = MatrixCell::build(&envs, "").unwrap();
+        assert_eq!(cells.len(), 2);
+        assert_eq!(cells[1].name(), "release");
+        assert_eq!(cells[1].envs(), &vec!["release".to_string()]);
+
+        let cells = MatrixCell::build(&envs, "ARCH=x86, arm").unwrap();
+        assert_eq!(cells.len(), 2);
+        assert_eq!(cells[1].name(), "ARCH=arm");
+        assert_eq!(cells[1].envs(), &envs);
+        let mut vars = VarSpace::default();
+        cells[1].apply(&mut vars);
+        assert!(vars.get("ARCH").is_some());
+
+        assert!(MatrixCell::build(&envs, "ARCH").is_err());
+        assert!(MatrixCell::build(&envs, "ARCH=").is_err());
+    }
+
+    #[test]
+    fn test_matrix_summary() {
+        let mut dev = Job::from("dev");
+        let mut task = Task::from("build");
+        task.finish();
+        dev.append(task);
+        let mut release = Job::from("release");
+        let mut task = Task::from("build");
+        task.err("exit 1\nmore".into());
+        release.append(task);
+        let text = matrix_summary(&[dev, release]);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with("dev      suc"));
+        assert!(lines[2].starts_with("release  fail"));
+        assert!(lines[2].ends_with("exit 1"));
+    }
+}
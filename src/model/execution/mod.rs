@@ -4,11 +4,17 @@ use crate::infra::once_init_log;
 use super::context::ExecContext;
 
 pub mod action;
+pub mod cache;
 pub mod global;
 pub mod hold;
 pub mod job;
+pub mod lock;
+pub mod matrix;
+pub mod retry;
 pub mod runnable;
 pub mod sequence;
+pub mod state;
+pub mod testing;
 pub mod trans;
 pub use dict::DictUse;
 pub use dict::VarSpace;
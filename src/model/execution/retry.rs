@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::Duration;
+
+use orion_common::friendly::AppendAble;
+use orion_error::{ErrorWith, WithContext};
+
+use crate::annotation::FST_ARG_TAG;
+use crate::execution::action::Action;
+use crate::execution::runnable::{ExecOut, TaskResult, TaskValue};
+use crate::execution::task::Task;
+use crate::util::duration::parse_duration;
+use crate::util::signal::is_interrupted;
+
+#[derive(Clone, Debug, PartialEq, Default)]
+pub enum Backoff {
+    #[default]
+    Fixed,
+    Exp,
+}
+
+/// 重试策略: count 为失败后的最大重试次数, 总尝试次数为 count + 1
+#[derive(Clone, Debug, PartialEq, Getters)]
+pub struct RetryPolicy {
+    count: u32,
+    delay: Duration,
+    backoff: Backoff,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            count: 3,
+            delay: Duration::from_secs(1),
+            backoff: Backoff::Fixed,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new(count: u32, delay: Duration, backoff: Backoff) -> Self {
+        Self {
+            count,
+            delay,
+            backoff,
+        }
+    }
+    /// 注解参数: #[retry(count=3, delay="5s", backoff="exp")] 或 #[retry(3)]
+    pub fn from_args(args: &HashMap<String, String>) -> Option<Self> {
+        let mut policy = Self::default();
+        if let Some(count) = args.get("count").or(args.get(FST_ARG_TAG)) {
+            policy.count = count.trim().parse().ok()?;
+        }
+        if let Some(delay) = args.get("delay") {
+            policy.delay = parse_duration(delay)?;
+        }
+        if let Some(backoff) = args.get("backoff") {
+            policy.backoff = match backoff.trim() {
+                "exp" => Backoff::Exp,
+                "fixed" => Backoff::Fixed,
+                _ => return None,
+            };
+        }
+        Some(policy)
+    }
+    /// 参数写法: retry : "3" 或 retry : "count=3,delay=5s,backoff=exp"
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut args = HashMap::new();
+        for item in text.split(',').map(str::trim).filter(|x| !x.is_empty()) {
+            match item.split_once('=') {
+                Some((k, v)) => args.insert(k.trim().to_string(), v.trim().to_string()),
+                None => args.insert(FST_ARG_TAG.to_string(), item.to_string()),
+            };
+        }
+        Self::from_args(&args)
+    }
+    /// 第 attempt 次失败后的等待时间
+    pub fn delay_of(&self, attempt: u32) -> Duration {
+        match self.backoff {
+            Backoff::Fixed => self.delay,
+            Backoff::Exp => self
+                .delay
+                .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1))),
+        }
+    }
+
+    /// 按策略重试执行;
+    /// 每次失败的尝试记录为一个 Action, 与最终成功的记录一起放入 Task
+    pub async fn retry_exec<F, Fut>(&self, name: &str, mut op: F) -> TaskResult
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = TaskResult>,
+    {
+        let mut task = Task::from(name.to_string());
+        let mut attempt = 1;
+        loop {
+            match op().await {
+                Ok(value) if task.actions().is_empty() => return Ok(value),
+                Ok(TaskValue { vars, rec, .. }) => {
+                    task.append(rec);
+                    task.finish();
+                    return Ok(TaskValue::from((vars, ExecOut::Task(task))));
+                }
+                // 中断后不再重试
+                Err(e) if attempt <= self.count && !is_interrupted() => {
+                    let wait = self.delay_of(attempt);
+                    warn!(target: "exec/retry", "{name} attempt {attempt} failed: {e}, retry after {wait:?}");
+                    let mut action = Action::from(format!("{name}#{attempt}"));
+                    action.stdout = e.to_string();
+                    action.err(e.to_string());
+                    task.record_attempt(action);
+                    tokio::time::sleep(wait).await;
+                    attempt += 1;
+                }
+                // 最终失败时把之前各次尝试的错误附在错误上下文中
+                Err(e) => {
+                    let mut attempts = WithContext::want("retry");
+                    for action in task.actions() {
+                        attempts.with(action.name().as_str(), action.stdout.as_str());
+                    }
+                    return Err(e).with(&attempts);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use orion_error::ToStructError;
+
+    use crate::{execution::VarSpace, ExecReason};
+
+    use super::*;
+
+    #[test]
+    fn test_parse_policy() {
+        let policy = RetryPolicy::parse("count=2,delay=10ms,backoff=exp").unwrap();
+        assert_eq!(
+            policy,
+            RetryPolicy::new(2, Duration::from_millis(10), Backoff::Exp)
+        );
+        assert_eq!(policy.delay_of(1), Duration::from_millis(10));
+        assert_eq!(policy.delay_of(3), Duration::from_millis(40));
+        assert_eq!(RetryPolicy::parse("5").unwrap().count(), &5);
+        assert!(RetryPolicy::parse("count=x").is_none());
+        assert!(RetryPolicy::parse("backoff=line").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_retry_exec() {
+        let policy = RetryPolicy::new(2, Duration::from_millis(1), Backoff::Fixed);
+        let calls = AtomicU32::new(0);
+        let value = policy
+            .retry_exec("flaky", || async {
+                if calls.fetch_add(1, Ordering::SeqCst) < 2 {
+                    Err(ExecReason::Args("flaky".into()).to_err())
+                } else {
+                    Ok(TaskValue::from((
+                        VarSpace::default(),
+                        ExecOut::Action(Action::from("flaky")),
+                    )))
+                }
+            })
+            .await
+            .unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        match value.rec {
+            ExecOut::Task(task) => assert_eq!(task.actions().len(), 3),
+            _ => panic!("need task"),
+        }
+
+        calls.store(0, Ordering::SeqCst);
+        let result = policy
+            .retry_exec("broken", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err::<TaskValue, _>(ExecReason::Args("broken".into()).to_err())
+            })
+            .await;
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        let context = result
+            .unwrap_err()
+            .context()
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert!(context.contains("broken#1"));
+        assert!(context.contains("broken#2"));
+    }
+}
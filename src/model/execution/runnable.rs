@@ -7,6 +7,7 @@ use crate::context::ExecContext;
 use crate::execution::task::Task;
 use crate::meta::GxlMeta;
 use crate::primitive::GxlAParams;
+use crate::sec::SecValueType;
 use crate::util::redirect::ReadSignal;
 use crate::ExecResult;
 
@@ -25,25 +26,44 @@ pub enum ExecOut {
     Ignore,
     Code(i32),
 }
+/// 块内流程控制, 沿块逐层向上传递;
+/// break/continue 由最近的循环处理, return 由函数调用处理
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockCtrl {
+    Break,
+    Continue,
+    Return(Option<SecValueType>),
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct TaskValue {
     pub vars: VarSpace,
 
     pub rec: ExecOut,
+    pub ctrl: Option<BlockCtrl>,
 }
 impl TaskValue {
     pub fn new(vars: VarSpace, task: ExecOut) -> Self {
-        Self { vars, rec: task }
+        Self {
+            vars,
+            rec: task,
+            ctrl: None,
+        }
     }
     pub fn rec(&self) -> &ExecOut {
         &self.rec
     }
+    pub fn with_ctrl(mut self, ctrl: Option<BlockCtrl>) -> Self {
+        self.ctrl = ctrl;
+        self
+    }
 }
 impl From<(VarSpace, ExecOut)> for TaskValue {
     fn from(value: (VarSpace, ExecOut)) -> Self {
         Self {
             vars: value.0,
             rec: value.1,
+            ctrl: None,
         }
     }
 }
@@ -53,6 +73,7 @@ impl From<(VarSpace, ExecOut, String)> for TaskValue {
         Self {
             vars: value.0,
             rec: value.1,
+            ctrl: None,
         }
     }
 }
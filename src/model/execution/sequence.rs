@@ -3,10 +3,10 @@ use std::sync::mpsc::Sender;
 
 use async_trait::async_trait;
 use orion_common::friendly::AppendAble;
-use orion_error::ErrorConv;
+use orion_error::{ErrorConv, ToStructError};
 
 use crate::ability::prelude::TaskValue;
-use crate::annotation::{Dryrunable, Transaction};
+use crate::annotation::{Dryrunable, Parallelable, Transaction};
 use crate::components::gxl_flow::meta::FlowMeta;
 use crate::components::gxl_spc::GxlSpace;
 use crate::context::ExecContext;
@@ -15,12 +15,16 @@ use crate::execution::hold::{ComHold, IsolationHold};
 use crate::execution::job::Job;
 use crate::execution::runnable::{AsyncRunnableTrait, ExecOut, TaskResult};
 use crate::execution::runnable::{AsyncRunnableWithSenderTrait, ComponentMeta};
+use crate::execution::state::{FlowState, UndoJournal};
 use crate::execution::task::Task;
-use crate::execution::trans::ComTrans;
+use crate::execution::trans::{report_rollback, ComTrans};
 use crate::execution::VarSpace;
 use crate::meta::{GxlMeta, MetaInfo};
 use crate::util::redirect::ReadSignal;
-use crate::ExecResult;
+use crate::util::signal::{is_interrupted, wait_interrupt};
+use crate::util::step::{self, StepDecision};
+use crate::util::task_report::task_local_report;
+use crate::{ExecError, ExecReason, ExecResult};
 
 use super::hold::TransableHold;
 use super::unit::{RunUnitGuard, RunUnitLable};
@@ -30,6 +34,8 @@ pub struct ExecSequence {
     name: String,
     filter: UniqueFilter,
     run_items: Vec<ComHold>,
+    state: Option<FlowState>,
+    journal: Option<UndoJournal>,
 }
 
 #[derive(Clone, Default)]
@@ -71,6 +77,17 @@ pub struct FlowSequence {
 
 pub trait HoldRunable: Dryrunable + AsyncRunnableTrait + Clone {}
 impl ExecSequence {
+    /// 记录执行进度; 已有进度时从第一个未完成的 hold 继续
+    pub fn with_state(mut self, state: FlowState) -> Self {
+        self.state = Some(state);
+        self
+    }
+    /// 事务内登记的 undo flow 同步写入日志
+    pub fn with_journal(mut self, journal: UndoJournal) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
     pub async fn execute(
         &self,
         ctx: ExecContext,
@@ -78,6 +95,19 @@ impl ExecSequence {
         spc: &impl SequLoader,
         sender: Option<Sender<ReadSignal>>,
     ) -> TaskResult {
+        self.execute_sequence(ctx, def, spc, sender)
+            .await
+            .map_err(|(e, ..)| e)
+    }
+
+    /// 失败时一并返回出错的 hold 名称及失败前的变量, 供 #[on_failure] 使用
+    pub async fn execute_traced(
+        &self,
+        ctx: ExecContext,
+        def: VarSpace,
+        spc: &impl SequLoader,
+        sender: Option<Sender<ReadSignal>>,
+    ) -> Result<TaskValue, (ExecError, String, VarSpace)> {
         self.execute_sequence(ctx, def, spc, sender).await
     }
 
@@ -88,7 +118,7 @@ impl ExecSequence {
         spc: &GxlSpace,
         sender: Option<Sender<ReadSignal>>,
     ) -> TaskResult {
-        self.execute_sequence(ctx, def, spc, sender).await
+        self.execute(ctx, def, spc, sender).await
     }
 
     async fn execute_sequence(
@@ -97,38 +127,106 @@ impl ExecSequence {
         mut def: VarSpace,
         spc: &impl SequLoader,
         sender: Option<Sender<ReadSignal>>,
-    ) -> TaskResult {
+    ) -> Result<TaskValue, (ExecError, String, VarSpace)> {
         warn!(target: ctx.path(), "sequence size: {}  dryrun: {}", self.run_items().len(), ctx.dryrun());
 
-        let mut trans_manage = ComTrans::new();
+        let mut trans_manage = ComTrans::new().with_journal(self.journal.clone());
         let mut job = Job::from(&self.name);
-        for (index, item) in self.run_items.iter().enumerate() {
-            info!(target: ctx.path(), "executing item {}: {} ", index, item.gxl_meta().full_name());
-            if trans_manage.in_transaction_trigger(item.is_transaction()) {
-                if let Some(undo) = item.undo_hold() {
-                    let mut sequ = ExecSequence::default();
-                    spc.find_flow(&undo, &mut sequ).err_conv()?;
-                    for undo in sequ.run_items() {
-                        info!(target: ctx.path(), "regist undo {}", undo.gxl_meta().name());
-                        trans_manage.add_undo_task(undo.clone(), def.clone());
+        let mut state = self.state.clone();
+        let mut index = 0;
+        let mut resume = None;
+        if let Some(state) = state.as_ref() {
+            let done = state.done_count(&self.run_items);
+            if done > 0 {
+                println!("resume from item {done}");
+                warn!(target: ctx.path(), "skip {done} finished flows");
+                resume = Some((done, state.clone()));
+            }
+        }
+        while index < self.run_items.len() {
+            let group = parallel_group(&self.run_items[index..]);
+            // 恢复运行时只跳过已完成的 flow, env 与属性重新执行以重新加载密文变量;
+            // 越过已完成部分后再叠加这些 flow 的输出
+            if let Some((done, finished)) = resume.as_ref() {
+                if index < *done && group.iter().all(|x| x.is_flow()) {
+                    index += group.len();
+                    continue;
+                }
+                if index >= *done {
+                    finished.restore(&mut def);
+                    resume = None;
+                }
+            }
+            let group_name = group
+                .iter()
+                .map(|x| x.gxl_meta().long_name())
+                .collect::<Vec<_>>()
+                .join(",");
+            for (offset, item) in group.iter().enumerate() {
+                info!(target: ctx.path(), "executing item {}: {} ", index + offset, item.gxl_meta().full_name());
+                if item.is_transaction() {
+                    let policy = item
+                        .undo_fail_policy()
+                        .map_err(|e| (e, group_name.clone(), def.clone()))?;
+                    trans_manage.set_undo_policy(policy);
+                }
+                if trans_manage.in_transaction_trigger(item.is_transaction()) {
+                    if let Some(undo) = item.undo_hold() {
+                        let mut sequ = ExecSequence::default();
+                        spc.find_flow(&undo, &mut sequ)
+                            .err_conv()
+                            .map_err(|e| (e, group_name.clone(), def.clone()))?;
+                        for undo in sequ.run_items() {
+                            info!(target: ctx.path(), "regist undo {}", undo.gxl_meta().name());
+                            trans_manage.add_undo_task(undo.clone(), def.clone());
+                        }
+                        trans_manage.journal_undo(undo.long_name(), &def);
                     }
                 }
             }
-            match self
-                .execute_hold(&ctx, &mut def, spc, item, sender.clone())
-                .await
-            {
+            let base = def.clone();
+            let result = if group.len() > 1 {
+                self.execute_parallel(&ctx, &mut def, spc, group, sender.clone())
+                    .await
+            } else {
+                self.execute_hold(&ctx, &mut def, spc, &group[0], sender.clone())
+                    .await
+            };
+            match result {
                 Ok(TaskValue { vars, rec, .. }) => {
                     def = vars;
                     job.append(rec);
+                    if let Some(state) = state.as_mut() {
+                        for (offset, item) in group.iter().enumerate() {
+                            let key = FlowState::hold_key(index + offset, item);
+                            let saved = if item.is_flow() {
+                                state.record_flow(key, &base, &def)
+                            } else {
+                                state.record(key)
+                            };
+                            if let Err(e) = saved {
+                                warn!(target: ctx.path(), "save run state failed: {e}");
+                            }
+                        }
+                    }
                 }
                 Err(e) => {
                     warn!("Sequence aborted : {e}");
-                    trans_manage.rollback(&ctx).await;
-                    return Err(e);
+                    let rollback = trans_manage.rollback(&ctx).await;
+                    if is_interrupted() {
+                        // 中断时保留已完成部分的报告
+                        task_local_report(ExecOut::Job(job));
+                    }
+                    report_rollback(rollback).await;
+                    return Err((e, group_name, def));
                 }
             }
+            index += group.len();
+        }
+        if let Some((_, finished)) = resume {
+            finished.restore(&mut def);
         }
+        trans_manage.commit();
 
         Ok(TaskValue::from((def, ExecOut::Job(job))))
     }
@@ -141,19 +239,108 @@ impl ExecSequence {
         item: &ComHold,
         sender: Option<Sender<ReadSignal>>,
     ) -> TaskResult {
+        let exec_queue = build_exec_queue(ctx, spc, item)?;
+        let value = run_exec_queue(
+            self.name.clone(),
+            ctx.clone(),
+            def.clone(),
+            exec_queue,
+            sender,
+        )
+        .await?;
+        *def = value.vars.clone();
+        Ok(value)
+    }
+
+    /// 并发执行一组 #[parallel] hold
+    /// 每个分支都从同一份 VarSpace 出发; 结束后按声明顺序合并各分支的变量变更,
+    /// 同名变量后声明者覆盖先声明者; Job 记录也按声明顺序追加。
+    /// 任一分支失败时, 等待其余分支结束后返回第一个错误。
+    async fn execute_parallel(
+        &self,
+        ctx: &ExecContext,
+        def: &mut VarSpace,
+        spc: &impl SequLoader,
+        group: &[ComHold],
+        sender: Option<Sender<ReadSignal>>,
+    ) -> TaskResult {
+        // 先构建全部执行队列再启动, 构建失败时不会留下已启动的分支
+        let queues = group
+            .iter()
+            .map(|item| build_exec_queue(ctx, spc, item))
+            .collect::<ExecResult<Vec<_>>>()?;
+        let mut handles = Vec::new();
+        for exec_queue in queues {
+            handles.push(tokio::spawn(run_exec_queue(
+                self.name.clone(),
+                ctx.clone(),
+                def.clone(),
+                exec_queue,
+                sender.clone(),
+            )));
+        }
+        let base = def.clone();
         let mut job = Job::from(&self.name);
-        let mut exec_queue = build_exec_queue(ctx, spc, item)?;
-        while let Some(item) = exec_queue.pop_back() {
-            let TaskValue { vars, rec, .. } = item
-                .async_exec(ctx.clone(), def.clone(), sender.clone())
-                .await?;
-            *def = vars;
-            job.append(rec);
+        let mut first_err = None;
+        for handle in handles {
+            match handle.await {
+                Ok(Ok(TaskValue { vars, rec, .. })) => {
+                    def.merge_branch(&base, vars);
+                    job.append(rec);
+                }
+                Ok(Err(e)) => {
+                    warn!(target: ctx.path(), "parallel item failed: {e}");
+                    first_err.get_or_insert(e);
+                }
+                Err(e) => {
+                    first_err.get_or_insert(
+                        ExecReason::Bug(format!("parallel item panic: {e}")).to_err(),
+                    );
+                }
+            }
+        }
+        if let Some(e) = first_err {
+            return Err(e);
         }
         Ok(TaskValue::new(def.clone(), ExecOut::Job(job)))
     }
 }
 
+/// 取出从头开始连续的 #[parallel] hold; 非并行 hold 单独成组
+fn parallel_group(items: &[ComHold]) -> &[ComHold] {
+    let count = items.iter().take_while(|x| x.is_parallel()).count();
+    &items[..count.max(1)]
+}
+
+async fn run_exec_queue(
+    name: String,
+    ctx: ExecContext,
+    mut def: VarSpace,
+    mut exec_queue: VecDeque<ComHold>,
+    sender: Option<Sender<ReadSignal>>,
+) -> TaskResult {
+    let mut job = Job::from(&name);
+    while let Some(item) = exec_queue.pop_back() {
+        let interrupted = || ExecReason::Interrupted(item.gxl_meta().full_name()).to_err();
+        if is_interrupted() {
+            return Err(interrupted());
+        }
+        if step::is_enabled() {
+            let what = item.gxl_meta().full_name();
+            if step::pause(ctx.path(), &what, &mut def)? == StepDecision::Skip {
+                continue;
+            }
+        }
+        let TaskValue { vars, rec, .. } = tokio::select! {
+            value = item.async_exec(ctx.clone(), def.clone(), sender.clone()) => value?,
+            _ = wait_interrupt() => return Err(interrupted()),
+        };
+        def = vars;
+        job.append(rec);
+    }
+    Ok(TaskValue::new(def, ExecOut::Job(job)))
+}
+
 fn build_exec_queue(
     ctx: &ExecContext,
     spc: &impl SequLoader,
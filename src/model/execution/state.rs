@@ -0,0 +1,381 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Number, Value};
+
+use crate::execution::hold::ComHold;
+use crate::execution::runnable::ComponentMeta;
+use crate::execution::trans::UndoFailPolicy;
+use crate::execution::VarSpace;
+use crate::meta::MetaInfo;
+use crate::sec::{SecFrom, SecValueType};
+use crate::var::UniCaseMap;
+use crate::{ExecReason, ExecResult};
+
+pub const STATE_DIR: &str = ".state";
+// 新运行开始时只保留最近的运行状态
+pub const KEEP_RUNS: usize = 20;
+const UNDO_JOURNAL: &str = "undo.json";
+
+/// 一次运行的状态目录: <root>/<run-id>
+#[derive(Clone, Debug, Getters)]
+pub struct RunStore {
+    root: PathBuf,
+    run_id: String,
+}
+
+impl RunStore {
+    pub fn create<P: AsRef<Path>>(root: P) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+            run_id: Local::now().format("%Y%m%d_%H%M%S_%3f").to_string(),
+        }
+    }
+    /// 恢复指定的运行; 未指定时取最近一次
+    pub fn resume<P: AsRef<Path>>(root: P, run_id: Option<&str>) -> ExecResult<Self> {
+        let root = root.as_ref().to_path_buf();
+        let run_id = match run_id.filter(|x| !x.is_empty()) {
+            Some(id) => id.to_string(),
+            None => Self::latest(&root)?,
+        };
+        if !root.join(&run_id).is_dir() {
+            return Err(ExecReason::Miss(format!("run state: {run_id}")).into());
+        }
+        Ok(Self { root, run_id })
+    }
+    fn latest(root: &Path) -> ExecResult<String> {
+        let entries = fs::read_dir(root).map_err(|e| ExecReason::Io(e.to_string()))?;
+        entries
+            .filter_map(|x| x.ok())
+            .filter(|x| x.path().is_dir())
+            .filter_map(|x| x.file_name().to_str().map(String::from))
+            .max()
+            .ok_or_else(|| ExecReason::Miss(format!("run state in {}", root.display())).into())
+    }
+    /// 只保留最近 keep 次运行的状态, 返回删除的数量
+    pub fn prune(&self, keep: usize) -> ExecResult<usize> {
+        if !self.root.is_dir() {
+            return Ok(0);
+        }
+        let entries = fs::read_dir(&self.root).map_err(|e| ExecReason::Io(e.to_string()))?;
+        let mut runs: Vec<String> = entries
+            .filter_map(|x| x.ok())
+            .filter(|x| x.path().is_dir())
+            .filter_map(|x| x.file_name().to_str().map(String::from))
+            .filter(|x| x != &self.run_id)
+            .collect();
+        runs.sort();
+        let count = runs.len().saturating_sub(keep.saturating_sub(1));
+        for run in &runs[..count] {
+            fs::remove_dir_all(self.root.join(run)).map_err(|e| ExecReason::Io(e.to_string()))?;
+        }
+        Ok(count)
+    }
+    pub fn run_path(&self) -> PathBuf {
+        self.root.join(&self.run_id)
+    }
+    /// 读取 flow 的状态; 不存在时返回空状态
+    pub fn flow_state(&self, flow: &str) -> ExecResult<FlowState> {
+        let path = self.run_path().join(format!("{flow}.json"));
+        let mut state: FlowState = load_json(&path)?;
+        state.path = path;
+        Ok(state)
+    }
+    pub fn undo_journal(&self) -> ExecResult<UndoJournal> {
+        let path = self.run_path().join(UNDO_JOURNAL);
+        let mut journal: UndoJournal = load_json(&path)?;
+        journal.path = path;
+        Ok(journal)
+    }
+}
+
+/// flow 执行进度: 已完成的 hold 与各 flow 产生的变量(不含密文变量)
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Getters)]
+pub struct FlowState {
+    #[serde(skip)]
+    path: PathBuf,
+    done: Vec<String>,
+    vars: Map<String, Value>,
+}
+
+impl FlowState {
+    pub fn hold_key(index: usize, item: &ComHold) -> String {
+        format!("{index}:{}", item.gxl_meta().full_name())
+    }
+    /// 从头开始与记录一致的已完成 hold 数量
+    pub fn done_count(&self, items: &[ComHold]) -> usize {
+        items
+            .iter()
+            .enumerate()
+            .zip(self.done.iter())
+            .take_while(|((index, item), key)| Self::hold_key(*index, item) == **key)
+            .count()
+    }
+    /// 叠加已完成 flow 的输出; env 与属性已在本次运行中重新加载
+    pub fn restore(&self, def: &mut VarSpace) {
+        restore_vars(&self.vars, def);
+    }
+    /// env 与属性 hold 只记录完成, 恢复时重新执行
+    pub fn record(&mut self, key: String) -> ExecResult<()> {
+        if !self.done.contains(&key) {
+            self.done.push(key);
+        }
+        save_json(&self.path, self)
+    }
+    /// 记录 flow 完成及其相对 base 的变量变更
+    pub fn record_flow(&mut self, key: String, base: &VarSpace, def: &VarSpace) -> ExecResult<()> {
+        for (k, v) in def.global().maps().iter() {
+            if base.global().maps().get(k) == Some(v) {
+                continue;
+            }
+            match value_to_json(v) {
+                Some(value) => self.vars.insert(k.to_string(), value),
+                None => self.vars.remove(&k.to_string()),
+            };
+        }
+        self.record(key)
+    }
+}
+
+/// 事务中登记的 undo flow 日志, 进程被杀后可通过 `gflow rollback <run-id>` 补偿
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Getters)]
+pub struct UndoJournal {
+    #[serde(skip)]
+    path: PathBuf,
+    entries: Vec<UndoEntry>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Getters)]
+pub struct UndoEntry {
+    flow: String,
+    vars: Map<String, Value>,
+    #[serde(default)]
+    policy: UndoFailPolicy,
+}
+
+impl UndoEntry {
+    pub fn restore(&self, def: &mut VarSpace) {
+        restore_vars(&self.vars, def);
+    }
+}
+
+impl UndoJournal {
+    pub fn push(&mut self, flow: String, def: &VarSpace, policy: UndoFailPolicy) -> ExecResult<()> {
+        self.entries.push(UndoEntry {
+            flow,
+            vars: snapshot_vars(def),
+            policy,
+        });
+        save_json(&self.path, self)
+    }
+    /// 取出最后登记的 undo, 执行前即落盘, 避免重复补偿
+    pub fn pop(&mut self) -> ExecResult<Option<UndoEntry>> {
+        let entry = self.entries.pop();
+        if entry.is_some() {
+            save_json(&self.path, self)?;
+        }
+        Ok(entry)
+    }
+    /// 放回未成功执行的 undo, entries 按登记顺序排列
+    pub fn restore(&mut self, entries: Vec<UndoEntry>) -> ExecResult<()> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        self.entries.extend(entries);
+        save_json(&self.path, self)
+    }
+    pub fn clear(&mut self) -> ExecResult<()> {
+        if self.entries.is_empty() {
+            return Ok(());
+        }
+        self.entries.clear();
+        save_json(&self.path, self)
+    }
+}
+
+fn save_json<T: Serialize>(path: &Path, data: &T) -> ExecResult<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| ExecReason::Io(e.to_string()))?;
+    }
+    let content = serde_json::to_string_pretty(data).map_err(|e| ExecReason::Io(e.to_string()))?;
+    fs::write(path, content).map_err(|e| ExecReason::Io(e.to_string()))?;
+    Ok(())
+}
+
+fn load_json<T: DeserializeOwned + Default>(path: &Path) -> ExecResult<T> {
+    if !path.exists() {
+        return Ok(T::default());
+    }
+    let content = fs::read_to_string(path).map_err(|e| ExecReason::Io(e.to_string()))?;
+    serde_json::from_str::<T>(&content)
+        .map_err(|e| ExecReason::Io(format!("{}: {e}", path.display())).into())
+}
+
+fn snapshot_vars(def: &VarSpace) -> Map<String, Value> {
+    def.global()
+        .maps()
+        .iter()
+        .filter_map(|(k, v)| value_to_json(v).map(|x| (k.to_string(), x)))
+        .collect()
+}
+
+fn restore_vars(vars: &Map<String, Value>, def: &mut VarSpace) {
+    let mut maps = UniCaseMap::new();
+    for (k, v) in vars {
+        if let Some(value) = json_to_value(v) {
+            maps.insert(k.clone().into(), value);
+        }
+    }
+    def.global_mut().merge(maps);
+}
+
+// 密文变量不落盘, 恢复时由启动环境重新提供
+pub(crate) fn value_to_json(value: &SecValueType) -> Option<Value> {
+    match value {
+        SecValueType::String(v) if !v.is_secret() => Some(Value::String(v.value().clone())),
+        SecValueType::Bool(v) if !v.is_secret() => Some(Value::Bool(*v.value())),
+        SecValueType::Number(v) if !v.is_secret() => Some(Value::Number((*v.value()).into())),
+        SecValueType::Float(v) if !v.is_secret() => Number::from_f64(*v.value()).map(Value::Number),
+        SecValueType::Ip(v) if !v.is_secret() => Some(Value::String(v.value().to_string())),
+        SecValueType::Obj(obj) => Some(Value::Object(
+            obj.iter()
+                .filter_map(|(k, v)| value_to_json(v).map(|x| (k.to_string(), x)))
+                .collect(),
+        )),
+        SecValueType::List(list) => Some(Value::Array(
+            list.iter().filter_map(value_to_json).collect(),
+        )),
+        _ => None,
+    }
+}
+
+fn json_to_value(value: &Value) -> Option<SecValueType> {
+    match value {
+        Value::String(v) => Some(SecValueType::nor_from(v.clone())),
+        Value::Bool(v) => Some(SecValueType::nor_from(*v)),
+        Value::Number(v) => v
+            .as_u64()
+            .map(SecValueType::nor_from)
+            .or_else(|| v.as_f64().map(SecValueType::nor_from)),
+        Value::Object(obj) => Some(SecValueType::Obj(
+            obj.iter()
+                .filter_map(|(k, v)| json_to_value(v).map(|x| (k.clone().into(), x)))
+                .collect(),
+        )),
+        Value::Array(list) => Some(SecValueType::List(
+            list.iter().filter_map(json_to_value).collect(),
+        )),
+        Value::Null => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use orion_variate::vars::ValueType;
+
+    use crate::traits::Setter;
+
+    use super::*;
+
+    #[test]
+    fn test_state_roundtrip() {
+        let root = std::env::temp_dir().join("gflow_state_test");
+        let _ = fs::remove_dir_all(&root);
+        let store = RunStore::create(&root);
+
+        let mut base = VarSpace::default();
+        base.global_mut().set("MODE", "release");
+        let mut def = base.clone();
+        def.global_mut().set("build", "ok");
+        def.global_mut().set("count", SecValueType::nor_from(3u64));
+        def.global_mut()
+            .sec_set("token", ValueType::from("secret".to_string()));
+        let mut state = store.flow_state("main.build").unwrap();
+        state.record("0:env.default".into()).unwrap();
+        state
+            .record_flow("1:main.build".into(), &base, &def)
+            .unwrap();
+        state.record("0:env.default".into()).unwrap();
+
+        let resumed = RunStore::resume(&root, None).unwrap();
+        assert_eq!(resumed.run_id(), store.run_id());
+        let state = resumed.flow_state("main.build").unwrap();
+        assert_eq!(
+            state.done(),
+            &vec!["0:env.default".to_string(), "1:main.build".to_string()]
+        );
+        // 只保存 flow 的非密文输出, env 变量在恢复时重新加载
+        assert!(!state.vars().contains_key("token"));
+        assert!(!state.vars().contains_key("MODE"));
+
+        let mut restored = VarSpace::default();
+        state.restore(&mut restored);
+        assert_eq!(
+            restored.get("build"),
+            Some(SecValueType::nor_from("ok".to_string()))
+        );
+        assert_eq!(restored.get("count"), Some(SecValueType::nor_from(3u64)));
+        assert!(RunStore::resume(&root, Some("none")).is_err());
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_prune_runs() {
+        let root = std::env::temp_dir().join("gflow_prune_test");
+        let _ = fs::remove_dir_all(&root);
+        for id in [
+            "20240101_000000_001",
+            "20240101_000000_002",
+            "20240101_000000_003",
+        ] {
+            fs::create_dir_all(root.join(id)).unwrap();
+        }
+        let store = RunStore::create(&root);
+        fs::create_dir_all(store.run_path()).unwrap();
+        assert_eq!(store.prune(2).unwrap(), 2);
+        assert!(root.join("20240101_000000_003").is_dir());
+        assert!(!root.join("20240101_000000_001").exists());
+        assert!(store.run_path().is_dir());
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_undo_journal() {
+        let root = std::env::temp_dir().join("gflow_undo_test");
+        let _ = fs::remove_dir_all(&root);
+        let store = RunStore::create(&root);
+        let mut def = VarSpace::default();
+        let mut journal = store.undo_journal().unwrap();
+        def.global_mut().set("step", "1");
+        journal
+            .push("main._undo_a".into(), &def, UndoFailPolicy::Continue)
+            .unwrap();
+        def.global_mut().set("step", "2");
+        journal
+            .push("main._undo_b".into(), &def, UndoFailPolicy::Abort)
+            .unwrap();
+
+        let mut journal = store.undo_journal().unwrap();
+        assert_eq!(journal.entries().len(), 2);
+        let last = journal.pop().unwrap().unwrap();
+        assert_eq!(last.flow(), "main._undo_b");
+        assert_eq!(last.policy(), &UndoFailPolicy::Abort);
+        let mut restored = VarSpace::default();
+        last.restore(&mut restored);
+        assert_eq!(
+            restored.get("step"),
+            Some(SecValueType::nor_from("2".to_string()))
+        );
+        assert_eq!(store.undo_journal().unwrap().entries().len(), 1);
+        journal.restore(vec![last]).unwrap();
+        let entries = store.undo_journal().unwrap().entries().clone();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].flow(), "main._undo_b");
+        journal.clear().unwrap();
+        assert!(store.undo_journal().unwrap().entries().is_empty());
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -14,6 +14,9 @@ pub struct Task {
     pub stdout: String,
     pub result: Result<RunningTime, String>,
     actions: Vec<Action>,
+    // 未执行的原因, 如 #[when] 条件不满足
+    #[serde(skip_serializing_if = "Option::is_none")]
+    skipped: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, PartialEq)]
@@ -43,6 +46,17 @@ impl Task {
     pub fn err(&mut self, msg: String) {
         self.result = Err(msg);
     }
+    pub fn skip(&mut self, reason: String) {
+        self.finish();
+        self.skipped = Some(reason);
+    }
+    pub fn is_skipped(&self) -> bool {
+        self.skipped.is_some()
+    }
+    // 记录失败的重试尝试, 不影响任务结果
+    pub fn record_attempt(&mut self, action: Action) {
+        self.actions.push(action);
+    }
 }
 
 impl From<String> for Task {
@@ -55,6 +69,7 @@ impl From<String> for Task {
                 running_time: String::new(),
             }),
             actions: vec![],
+            skipped: None,
         }
     }
 }
@@ -66,6 +81,7 @@ impl From<&String> for Task {
             stdout: String::new(),
             result: Err("unknow".into()),
             actions: vec![],
+            skipped: None,
         }
     }
 }
@@ -78,6 +94,7 @@ impl From<&str> for Task {
             stdout: String::new(),
             result: Err("unknow".into()),
             actions: vec![],
+            skipped: None,
         }
     }
 }
@@ -131,4 +148,8 @@ fn build_action() {
     task1.finish();
     let mut task2 = Task::from("test.name");
     task2.err("bad".into());
+    let mut task3 = Task::from("test.name");
+    task3.skip("when: false".into());
+    assert!(task3.is_skipped());
+    assert!(task3.result().is_ok());
 }
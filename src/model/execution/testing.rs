@@ -0,0 +1,193 @@
+use std::fmt::Write;
+use std::time::Duration;
+
+use orion_common::friendly::AppendAble;
+
+use super::job::Job;
+
+/// 一个 #[test] flow 的执行结果; #[should_fail] 时执行失败才算通过
+#[derive(Debug, Clone, Getters)]
+pub struct TestCase {
+    job: Job,
+    should_fail: bool,
+    error: Option<String>,
+    time: Duration,
+}
+
+impl TestCase {
+    pub fn new(job: Job, should_fail: bool, error: Option<String>, time: Duration) -> Self {
+        Self {
+            job,
+            should_fail,
+            error,
+            time,
+        }
+    }
+    pub fn name(&self) -> &str {
+        self.job.name()
+    }
+    pub fn is_pass(&self) -> bool {
+        self.error.is_some() == self.should_fail
+    }
+    /// 未通过的原因
+    pub fn message(&self) -> Option<String> {
+        match (&self.error, self.should_fail) {
+            (Some(err), false) => Some(err.clone()),
+            (None, true) => Some("should fail, but passed".into()),
+            _ => None,
+        }
+    }
+    pub fn line(&self) -> String {
+        let status = if self.is_pass() { "ok" } else { "FAILED" };
+        format!("test {} ... {status} ({:?})", self.name(), self.time)
+    }
+}
+
+#[derive(Debug, Clone, Default, Getters)]
+pub struct TestReport {
+    cases: Vec<TestCase>,
+}
+
+impl AppendAble<TestCase> for TestReport {
+    fn append(&mut self, case: TestCase) {
+        self.cases.push(case);
+    }
+}
+
+impl TestReport {
+    pub fn is_suc(&self) -> bool {
+        self.cases.iter().all(|x| x.is_pass())
+    }
+    pub fn failed(&self) -> Vec<&str> {
+        self.cases
+            .iter()
+            .filter(|x| !x.is_pass())
+            .map(|x| x.name())
+            .collect()
+    }
+    fn total_time(&self) -> Duration {
+        self.cases.iter().map(|x| x.time).sum()
+    }
+
+    pub fn summary(&self) -> String {
+        let mut out = String::new();
+        for case in self.cases.iter().filter(|x| !x.is_pass()) {
+            let msg = case.message().unwrap_or_default();
+            let _ = writeln!(out, "---- {} ----\n{}\n", case.name(), msg.trim_end());
+        }
+        let failed = self.failed().len();
+        let status = if failed == 0 { "ok" } else { "FAILED" };
+        let _ = write!(
+            out,
+            "test result: {status}. {} passed; {failed} failed; finished in {:?}",
+            self.cases.len() - failed,
+            self.total_time()
+        );
+        out
+    }
+
+    /// JUnit XML, 以 mod 名作为 classname
+    pub fn to_junit(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+        let _ = writeln!(
+            out,
+            r#"<testsuite name="gflow" tests="{}" failures="{}" time="{:.3}">"#,
+            self.cases.len(),
+            self.failed().len(),
+            self.total_time().as_secs_f64()
+        );
+        for case in &self.cases {
+            let (class, name) = case.name().split_once('.').unwrap_or(("", case.name()));
+            let _ = write!(
+                out,
+                r#"  <testcase classname="{}" name="{}" time="{:.3}""#,
+                xml_escape(class),
+                xml_escape(name),
+                case.time.as_secs_f64()
+            );
+            match case.message() {
+                Some(msg) => {
+                    let first = msg.lines().next().unwrap_or_default();
+                    let _ = writeln!(out, ">");
+                    let _ = writeln!(
+                        out,
+                        r#"    <failure message="{}">{}</failure>"#,
+                        xml_escape(first),
+                        xml_escape(&msg)
+                    );
+                    let _ = writeln!(out, "  </testcase>");
+                }
+                None => {
+                    let _ = writeln!(out, "/>");
+                }
+            }
+        }
+        let _ = writeln!(out, "</testsuite>");
+        out
+    }
+}
+
+fn xml_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn case(name: &str, should_fail: bool, error: Option<&str>) -> TestCase {
+        TestCase::new(
+            Job::from(name),
+            should_fail,
+            error.map(String::from),
+            Duration::from_millis(5),
+        )
+    }
+
+    #[test]
+    fn test_report() {
+        let mut report = TestReport::default();
+        report.append(case("main.test_add", false, None));
+        report.append(case("main.test_bad", true, Some("assert <1.3> failed")));
+        assert!(report.is_suc());
+        assert!(report
+            .summary()
+            .starts_with("test result: ok. 2 passed; 0 failed;"));
+
+        report.append(case(
+            "sys.test_err",
+            false,
+            Some("cmd \"exit 1\" failed\ncode: 1"),
+        ));
+        report.append(case("main.test_pass", true, None));
+        assert!(!report.is_suc());
+        assert_eq!(report.failed(), vec!["sys.test_err", "main.test_pass"]);
+        assert!(report
+            .summary()
+            .contains("---- main.test_pass ----\nshould fail, but passed"));
+        assert!(report
+            .summary()
+            .ends_with("2 passed; 2 failed; finished in 20ms"));
+
+        let xml = report.to_junit();
+        assert!(xml.contains(r#"<testsuite name="gflow" tests="4" failures="2" time="0.020">"#));
+        assert!(xml.contains(r#"<testcase classname="main" name="test_add" time="0.005"/>"#));
+        assert!(xml.contains(
+            r#"<failure message="cmd &quot;exit 1&quot; failed">cmd &quot;exit 1&quot; failed
+code: 1</failure>"#
+        ));
+        assert_eq!(xml.matches("<failure").count(), 2);
+    }
+}
@@ -1,11 +1,43 @@
 use std::collections::VecDeque;
 
+use orion_common::friendly::AppendAble;
+use serde::{Deserialize, Serialize};
+
 use crate::{
-    ability::prelude::ComponentMeta, context::ExecContext,
-    execution::runnable::AsyncRunnableWithSenderTrait,
+    ability::prelude::ComponentMeta,
+    context::ExecContext,
+    execution::runnable::{AsyncRunnableWithSenderTrait, ExecOut},
+    task_report::{
+        task_notification::TaskNotice,
+        task_rc_config::{build_task_url, report_enable, TaskUrlType},
+        task_result_report::TaskReport,
+    },
+    util::{
+        http_handle::{create_and_send_task_notice, send_http_request},
+        task_report::task_local_report,
+    },
 };
 
-use super::{hold::ComHold, VarSpace};
+use super::{hold::ComHold, job::Job, state::UndoJournal, task::Task, VarSpace};
+
+/// undo 执行失败时的处理: continue 继续执行剩余 undo, abort 放弃剩余 undo
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UndoFailPolicy {
+    #[default]
+    Continue,
+    Abort,
+}
+
+impl UndoFailPolicy {
+    pub fn parse(text: &str) -> Option<Self> {
+        match text.trim() {
+            "continue" => Some(Self::Continue),
+            "abort" => Some(Self::Abort),
+            _ => None,
+        }
+    }
+}
 
 // 事务管理器，跟踪事务状态和撤销任务
 #[derive(Clone, Default)]
@@ -14,7 +46,9 @@ where
     T: AsyncRunnableWithSenderTrait,
 {
     in_transaction: bool,
-    undo_stack: VecDeque<(T, VarSpace)>, // 存储待撤销的任务
+    undo_stack: VecDeque<(T, VarSpace, UndoFailPolicy)>, // 存储待撤销的任务及登记时的失败策略
+    journal: Option<UndoJournal>,                        // 落盘的 undo 日志
+    undo_policy: UndoFailPolicy,
 }
 
 impl<T> TransactionManager<T>
@@ -25,8 +59,14 @@ where
         Self {
             in_transaction: false,
             undo_stack: VecDeque::new(),
+            journal: None,
+            undo_policy: UndoFailPolicy::default(),
         }
     }
+    pub fn with_journal(mut self, journal: Option<UndoJournal>) -> Self {
+        self.journal = journal;
+        self
+    }
 
     pub fn begin_transaction(&mut self) {
         warn!(target: "trans", "transaction begin");
@@ -39,33 +79,107 @@ where
         self.in_transaction
     }
 
+    /// 对之后登记的 undo 生效, 已登记的 undo 保留各自的策略
+    pub fn set_undo_policy(&mut self, policy: UndoFailPolicy) {
+        self.undo_policy = policy;
+    }
+
     pub fn in_transaction(&self) -> bool {
         self.in_transaction
     }
 
     pub fn add_undo_task(&mut self, task: T, vars: VarSpace) {
         if self.in_transaction {
-            self.undo_stack.push_back((task, vars));
+            self.undo_stack.push_back((task, vars, self.undo_policy));
+        }
+    }
+
+    /// 记录 undo flow 到日志, 供进程异常退出后 `gflow rollback` 使用
+    pub fn journal_undo(&mut self, flow: String, vars: &VarSpace) {
+        if !self.in_transaction {
+            return;
+        }
+        if let Some(journal) = self.journal.as_mut() {
+            if let Err(e) = journal.push(flow, vars, self.undo_policy) {
+                warn!(target: "trans", "write undo journal failed: {e}");
+            }
         }
     }
+    /// 事务正常结束, 清除日志
+    pub fn commit(&mut self) {
+        self.clear_journal();
+    }
 
-    pub async fn rollback(&mut self, ctx: &ExecContext) {
-        while let Some((undo, dict)) = self.undo_stack.pop_back() {
+    /// 逆序执行 undo, 每个 undo 记录为 rollback Job 中的一个 Task
+    pub async fn rollback(&mut self, ctx: &ExecContext) -> Job {
+        let mut job = Job::from("rollback");
+        while let Some((undo, dict, policy)) = self.undo_stack.pop_back() {
+            let name = undo.gxl_meta().name().to_string();
+            let mut task = Task::from(format!("undo:{name}"));
             match undo.async_exec(ctx.clone(), dict, None).await {
-                Ok(_) => warn!("Undo successful for {}", undo.gxl_meta().name()),
-                Err(e) => error!("Undo failed for {}: {}", undo.gxl_meta().name(), e),
+                Ok(value) => {
+                    warn!("Undo successful for {name}");
+                    task.append(value.rec);
+                    task.finish();
+                    job.append(task);
+                }
+                Err(e) => {
+                    error!("Undo failed for {name}: {e}");
+                    task.err(e.to_string());
+                    job.append(task);
+                    if policy == UndoFailPolicy::Abort {
+                        warn!(target: "trans", "rollback aborted, skip {} undo", self.undo_stack.len());
+                        while let Some((skip, ..)) = self.undo_stack.pop_back() {
+                            let mut task = Task::from(format!("undo:{}", skip.gxl_meta().name()));
+                            task.err("skipped: rollback aborted".to_string());
+                            job.append(task);
+                        }
+                    }
+                }
+            }
+        }
+        self.clear_journal();
+        job
+    }
+    fn clear_journal(&mut self) {
+        if let Some(journal) = self.journal.as_mut() {
+            if let Err(e) = journal.clear() {
+                warn!(target: "trans", "clear undo journal failed: {e}");
             }
         }
     }
 }
 pub type ComTrans = TransactionManager<ComHold>;
 
+/// rollback 结果写入本地报告, 开启报告中心时逐个上报补偿任务
+pub async fn report_rollback(job: Job) {
+    if job.tasks().is_empty() {
+        return;
+    }
+    if report_enable().await {
+        let url = build_task_url(TaskUrlType::TaskReport)
+            .await
+            .unwrap_or_default();
+        for task in job.tasks() {
+            match create_and_send_task_notice(task, &TaskNotice::new()).await {
+                Ok(notice) => {
+                    let report = TaskReport::from_task_and_notice(task.clone(), notice);
+                    send_http_request(report, &url).await;
+                }
+                Err(e) => warn!(target: "trans", "report rollback task failed: {e}"),
+            }
+        }
+    }
+    task_local_report(ExecOut::Job(job));
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
         ability::prelude::{AsyncRunnableTrait, ExecOut, TaskResult, TaskValue},
         context::ExecContext,
+        execution::state::RunStore,
         meta::GxlMeta,
         util::redirect::ReadSignal,
         ExecError,
@@ -185,9 +299,76 @@ mod tests {
         manager.add_undo_task(bad_task.clone(), VarSpace::default());
 
         // Rollback should execute both tasks even if one fails
-        manager.rollback(&ctx).await;
+        let job = manager.rollback(&ctx).await;
 
         assert_eq!(*good_task.execute_count.lock().unwrap(), 1);
         assert_eq!(*bad_task.execute_count.lock().unwrap(), 1);
+        assert_eq!(job.tasks().len(), 2);
+        assert!(job.tasks()[0].result().is_err());
+        assert!(job.tasks()[1].result().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_rollback_abort_policy() {
+        let mut manager = TransactionManager::new();
+        manager.begin_transaction();
+        manager.set_undo_policy(UndoFailPolicy::Abort);
+
+        let ctx = ExecContext::default();
+        let good_task = MockTask::new("good", false);
+        let bad_task = MockTask::new("bad", true);
+
+        manager.add_undo_task(good_task.clone(), VarSpace::default());
+        manager.add_undo_task(bad_task.clone(), VarSpace::default());
+
+        // 之后登记的 undo 使用新的策略, 不影响已登记的 undo
+        manager.set_undo_policy(UndoFailPolicy::Continue);
+        let late_task = MockTask::new("late", true);
+        manager.add_undo_task(late_task.clone(), VarSpace::default());
+
+        let job = manager.rollback(&ctx).await;
+        assert_eq!(*late_task.execute_count.lock().unwrap(), 1);
+        assert_eq!(*bad_task.execute_count.lock().unwrap(), 1);
+        assert_eq!(*good_task.execute_count.lock().unwrap(), 0);
+        assert_eq!(job.tasks().len(), 3);
+        assert_eq!(job.tasks()[2].name(), "undo:meta:good");
+        assert!(job.tasks()[2].result().is_err());
+        assert!(manager.undo_stack.is_empty());
+    }
+
+    #[test]
+    fn test_undo_fail_policy_parse() {
+        assert_eq!(UndoFailPolicy::parse("abort"), Some(UndoFailPolicy::Abort));
+        assert_eq!(
+            UndoFailPolicy::parse(" continue "),
+            Some(UndoFailPolicy::Continue)
+        );
+        assert_eq!(UndoFailPolicy::parse("retry"), None);
+    }
+
+    #[tokio::test]
+    async fn test_undo_journal_lifecycle() {
+        let root = std::env::temp_dir().join("gflow_trans_journal");
+        let _ = std::fs::remove_dir_all(&root);
+        let store = RunStore::create(&root);
+        let mut manager: TransactionManager<MockTask> =
+            TransactionManager::new().with_journal(Some(store.undo_journal().unwrap()));
+
+        // 事务开始前不记录
+        manager.journal_undo("main._undo_a".into(), &VarSpace::default());
+        assert!(store.undo_journal().unwrap().entries().is_empty());
+
+        manager.begin_transaction();
+        manager.journal_undo("main._undo_a".into(), &VarSpace::default());
+        manager.set_undo_policy(UndoFailPolicy::Abort);
+        manager.journal_undo("main._undo_b".into(), &VarSpace::default());
+        let entries = store.undo_journal().unwrap().entries().clone();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].policy(), &UndoFailPolicy::Continue);
+        assert_eq!(entries[1].policy(), &UndoFailPolicy::Abort);
+
+        manager.rollback(&ExecContext::default()).await;
+        assert!(store.undo_journal().unwrap().entries().is_empty());
+        let _ = std::fs::remove_dir_all(&root);
     }
 }
@@ -7,6 +7,7 @@ pub enum RunUnitLable {
     Entry(String),
     Exist(String),
     ModProp(String),
+    Hook(String),
     Flow,
 }
 #[derive(Debug, Clone, Getters)]
@@ -33,6 +34,12 @@ impl RunUnitGuard {
             open: true,
         }
     }
+    pub fn from_hook(value: &FlowMeta) -> Self {
+        Self {
+            lable: RunUnitLable::Hook(value.long_name()),
+            open: true,
+        }
+    }
     pub fn from_mod(value: &ModMeta) -> Self {
         Self {
             lable: RunUnitLable::ModProp(value.long_name()),
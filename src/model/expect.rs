@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::time::Duration;
 pub type StrMap = HashMap<String, String>;
 
 pub trait Mustable {
@@ -28,6 +29,14 @@ pub struct ShellOption {
     pub secrecy: bool,
     pub expect: Vec<i32>,
     pub log_lev: Option<log::Level>,
+    //超时后杀掉整个进程组
+    pub timeout: Option<Duration>,
+    //实时输出时的行前缀, 一般为 flow 的执行路径
+    pub prefix: Option<String>,
+    //执行目录, 环境变量覆盖与标准输入, 执行前求值
+    pub dir: Option<String>,
+    pub env: Vec<(String, String)>,
+    pub stdin: Option<String>,
 }
 impl Default for ShellOption {
     fn default() -> Self {
@@ -46,6 +55,11 @@ impl ShellOption {
             secrecy: false,
             expect: vec![0],
             log_lev: Some(log::Level::Info),
+            timeout: None,
+            prefix: None,
+            dir: None,
+            env: Vec::new(),
+            stdin: None,
         }
     }
     pub fn new_explicit(outer: bool, inner: bool) -> Self {
@@ -58,6 +72,11 @@ impl ShellOption {
             secrecy: false,
             expect: vec![0],
             log_lev: Some(log::Level::Info),
+            timeout: None,
+            prefix: None,
+            dir: None,
+            env: Vec::new(),
+            stdin: None,
         }
     }
     pub fn quiet(&self, scope: LogicScope) -> bool {
@@ -20,6 +20,7 @@ pub enum SubTaskStatus {
     Inprogress,
     Success,
     Failure,
+    Skipped,
 }
 
 impl TaskReport {
@@ -30,6 +31,7 @@ impl TaskReport {
             name: task.name().clone(),
             log: task.stdout.clone(),
             status: match task.result() {
+                Ok(_) if task.is_skipped() => SubTaskStatus::Skipped,
                 Ok(_) => SubTaskStatus::Success,
                 Err(_) => SubTaskStatus::Failure,
             },
@@ -37,3 +39,30 @@ impl TaskReport {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report_status(task: Task) -> SubTaskStatus {
+        TaskReport::from_task_and_notice(task, TaskNotice::new()).status
+    }
+
+    #[test]
+    fn test_report_status() {
+        let mut task = Task::from("build");
+        task.finish();
+        assert_eq!(report_status(task), SubTaskStatus::Success);
+
+        let mut task = Task::from("deploy");
+        task.err("exit 1".into());
+        assert_eq!(report_status(task), SubTaskStatus::Failure);
+
+        let mut task = Task::from("lint");
+        task.skip("when: false".into());
+        let report = TaskReport::from_task_and_notice(task, TaskNotice::new());
+        assert_eq!(report.status, SubTaskStatus::Skipped);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["status"], "Skipped");
+    }
+}
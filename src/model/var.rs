@@ -108,6 +108,14 @@ impl VarDict {
             self.maps.insert(k, v);
         }
     }
+    /// 仅合并 branch 相对 base 新增或修改过的变量
+    pub fn merge_changes(&mut self, base: &Self, branch: Self) {
+        for (k, v) in branch.maps {
+            if base.maps.get(&k) != Some(&v) {
+                self.maps.insert(k, v);
+            }
+        }
+    }
     pub fn merge_item_obj(&mut self, key: &str, obj: SecValueObj) {
         if let Some(SecValueType::Obj(found)) = self.maps.get_mut(&key.to_unicase()) {
             for (k, v) in obj {
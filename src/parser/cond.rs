@@ -1,7 +1,7 @@
 use super::abilities::define::gal_gxl_object;
 use super::inner::funs::gal_defined;
 use super::prelude::*;
-use orion_parse::define::take_var_ref_name;
+use orion_parse::define::{take_sq_string, take_var_ref_name};
 use orion_parse::symbol::{
     symbol_bracket_beg, symbol_bracket_end, symbol_cmp, symbol_logic_and, symbol_logic_not,
     symbol_logic_or, LogicSymbol,
@@ -16,6 +16,7 @@ use crate::parser::atom::spaced;
 use crate::parser::domain::gal_keyword;
 use crate::parser::stc_blk::gal_block;
 use crate::primitive::GxlObject;
+use crate::sec::{SecFrom, SecValueType};
 
 pub fn gal_else_if(input: &mut &str) -> Result<GxlCond> {
     skip_spaces_block(input)?;
@@ -38,11 +39,20 @@ pub fn gal_else_if(input: &mut &str) -> Result<GxlCond> {
     Ok(GxlCond::new(ctrl_express))
 }
 
+// 比较值; 单引号字符串用于 #[when("...")] 等写在双引号中的表达式
+fn gal_cmp_value(input: &mut &str) -> Result<GxlObject> {
+    alt((
+        take_sq_string.map(|x| GxlObject::from(SecValueType::nor_from(x))),
+        gal_gxl_object,
+    ))
+    .parse_next(input)
+}
+
 pub fn gal_cmp_exp(input: &mut &str) -> Result<ExpressEnum> {
     let (name, cmp, value) = (
         spaced(take_var_ref_name).context(wn_desc("<env-var>")),
         spaced(symbol_cmp).context(wn_desc("operator")),
-        spaced(gal_gxl_object).context(wn_desc("<value-str>")),
+        spaced(gal_cmp_value).context(wn_desc("<value-str>")),
     )
         .parse_next(input)?;
     Ok(ExpressEnum::Cmp(CmpExpress::from_op(
@@ -170,6 +180,14 @@ mod tests {
         let exp = run_gxl(gal_exp, &mut data).assert();
         assert!(exp.decide(ExecContext::default(), &dict).assert());
 
+        // 单引号字符串, 可包含撇号与双引号
+        dict.global_mut()
+            .set("msg", SecValueType::nor_from(r#"it's "ok""#.to_string()));
+        let mut data = r#" ${msg} == 'it\'s "ok"' && ${val_s} == '1'"#;
+        let exp = run_gxl(gal_exp, &mut data).assert();
+        assert!(exp.decide(ExecContext::default(), &dict).assert());
+        assert_eq!(data, "");
+
         let mut data = r#" ${val} != 1"#;
         let exp = run_gxl(gal_exp, &mut data).assert();
         assert!(!exp.decide(ExecContext::default(), &dict).assert());
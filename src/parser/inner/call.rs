@@ -1,17 +1,22 @@
 use super::super::prelude::*;
-use orion_parse::symbol::symbol_comma;
-use winnow::combinator::separated;
+use orion_parse::atom::take_var_name;
+use orion_parse::symbol::{symbol_assign, symbol_colon, symbol_comma};
+use winnow::combinator::{separated, terminated};
 
 use crate::ability::delegate::ActCall;
+use crate::parser::abilities::define::take_value_map;
 use crate::parser::abilities::param::gal_actual_param;
+use crate::parser::atom::spaced;
 use crate::parser::domain::{gal_call_beg, gal_call_end, gal_var_input};
 use crate::primitive::GxlAParam;
 pub fn gal_call(input: &mut &str) -> Result<ActCall> {
+    // VER = ver.current();
+    let ret_name = opt(terminated(take_var_name, spaced(symbol_assign))).parse_next(input)?;
     let name = take_var_path
         .context(wn_desc("<call-name>"))
         .parse_next(input)?;
     let args = fun_call_args.parse_next(input)?;
-    let dto = ActCall::from((name, args));
+    let dto = ActCall::from((name, args)).with_ret_name(ret_name.map(String::from));
     Ok(dto)
 }
 
@@ -26,11 +31,30 @@ pub fn fun_call_args(input: &mut &str) -> Result<Vec<GxlAParam>> {
 
 pub fn action_call_args(input: &mut &str) -> Result<Vec<(String, String)>> {
     gal_call_beg.parse_next(input)?;
-    let props: Vec<(String, String)> =
-        separated(0.., gal_var_input, symbol_comma).parse_next(input)?;
+    let props: Vec<Vec<(String, String)>> = separated(
+        0..,
+        alt((gal_obj_input, gal_var_input.map(|x| vec![x]))),
+        symbol_comma,
+    )
+    .parse_next(input)?;
     opt(symbol_comma).parse_next(input)?;
     gal_call_end.parse_next(input)?;
-    Ok(props)
+    Ok(props.into_iter().flatten().collect())
+}
+
+/// 对象参数 key : { K : "v" } 展开为 ("key.K", "v")
+fn gal_obj_input(input: &mut &str) -> Result<Vec<(String, String)>> {
+    let _ = multispace0.parse_next(input)?;
+    let key = take_while(1.., ('0'..='9', 'A'..='Z', 'a'..='z', ['_', '.']))
+        .context(wn_desc("<var-name>"))
+        .parse_next(input)?;
+    symbol_colon.parse_next(input)?;
+    let obj = take_value_map.parse_next(input)?;
+    multispace0(input)?;
+    Ok(obj
+        .iter()
+        .map(|(k, v)| (format!("{key}.{k}"), v.to_string()))
+        .collect())
 }
 
 #[cfg(test)]
@@ -70,4 +94,29 @@ mod tests {
         assert_eq!(found.actual_params(), expect.actual_params());
         assert_eq!(data, "");
     }
+
+    #[test]
+    fn call_ret_test() {
+        let mut data = r#"
+             VER = ver.current ( base : "1.0" ) ;"#;
+        let found = run_gxl(gal_call, &mut data).assert();
+        assert_eq!(found.name(), "ver.current");
+        assert_eq!(found.ret_name(), &Some("VER".to_string()));
+        assert_eq!(data, "");
+    }
+
+    #[test]
+    fn action_args_obj_test() {
+        let mut data = r#"( "make", env : { CC : "gcc", Mode: "${MODE}" }, dir : "./sub" ) ;"#;
+        let found = run_gxl(action_call_args, &mut data).assert();
+        assert_eq!(
+            found,
+            vec![
+                ("default".to_string(), "make".to_string()),
+                ("env.CC".to_string(), "gcc".to_string()),
+                ("env.Mode".to_string(), "${MODE}".to_string()),
+                ("dir".to_string(), "./sub".to_string()),
+            ]
+        );
+    }
 }
@@ -3,6 +3,7 @@ use super::prelude::*;
 
 use crate::ability::cmd::GxCmdDtoBuilder;
 use crate::ability::GxCmd;
+use crate::execution::retry::RetryPolicy;
 use crate::expect::ShellOption;
 use crate::parser::domain::{gal_keyword, gal_keyword_alt};
 
@@ -16,8 +17,15 @@ pub fn gal_cmd(input: &mut &str) -> Result<GxCmd> {
         let key = one.0.to_lowercase();
         if key == "default" || key == "cmd" {
             builder.cmd(one.1);
+        } else if key == "retry" {
+            match RetryPolicy::parse(one.1.as_str()) {
+                Some(policy) => {
+                    builder.retry(Some(policy));
+                }
+                None => return fail.context(wn_desc("<retry>")).parse_next(input),
+            }
         } else {
-            shell_opt_setting(key, one.1, &mut expect);
+            shell_opt_setting(one.0, one.1, &mut expect, input)?;
         }
     }
     builder.expect(expect);
@@ -124,6 +132,62 @@ mod tests {
         assert_eq!(obj, GxCmd::dto_new(xpt));
     }
     #[test]
+    fn cmd_test_timeout() {
+        let expect = ShellOption {
+            timeout: Some(std::time::Duration::from_secs(30)),
+            ..Default::default()
+        };
+        let mut data = r#"
+             gx.cmd( "${PRJ_ROOT}/do.sh", timeout : "30s" ) ;"#;
+        let obj = gal_cmd(&mut data).assert();
+        let xpt = GxCmdDtoBuilder::default()
+            .cmd("${PRJ_ROOT}/do.sh".into())
+            .expect(expect)
+            .build()
+            .unwrap();
+        assert_eq!(data, "");
+        assert_eq!(obj, GxCmd::dto_new(xpt));
+        let mut data = r#"
+             gx.cmd( "${PRJ_ROOT}/do.sh", timeout : "30 seconds" ) ;"#;
+        assert!(gal_cmd(&mut data).is_err());
+    }
+    #[test]
+    fn cmd_test_retry() {
+        let mut data = r#"
+             gx.cmd( "git fetch", retry : "count=3,delay=5s,backoff=exp" ) ;"#;
+        let obj = gal_cmd(&mut data).assert();
+        assert_eq!(data, "");
+        assert_eq!(
+            obj.dto().retry,
+            RetryPolicy::parse("count=3,delay=5s,backoff=exp")
+        );
+        let mut data = r#"
+             gx.cmd( "git fetch", retry : "three" ) ;"#;
+        assert!(gal_cmd(&mut data).is_err());
+    }
+    #[test]
+    fn cmd_test_dir_env_stdin() {
+        let expect = ShellOption {
+            dir: Some("./sub".into()),
+            env: vec![
+                ("CC".into(), "gcc".into()),
+                ("BUILD_MODE".into(), "${MODE}".into()),
+            ],
+            stdin: Some("${DATA}".into()),
+            ..Default::default()
+        };
+        let mut data = r#"
+             gx.cmd( "make", dir : "./sub", env : { CC : "gcc", BUILD_MODE : "${MODE}" }, stdin : "${DATA}" ) ;"#;
+        let obj = gal_cmd(&mut data).assert();
+        let xpt = GxCmdDtoBuilder::default()
+            .cmd("make".into())
+            .expect(expect)
+            .build()
+            .unwrap();
+        assert_eq!(data, "");
+        assert_eq!(obj, GxCmd::dto_new(xpt));
+    }
+    #[test]
     fn cmd_test_raw_string() {
         let expect = ShellOption::default();
         let mut data = "
@@ -6,6 +6,7 @@ use crate::components::{gxl_var::*, GxlProps};
 use crate::expect::ShellOption;
 use crate::parser::abilities::define::gal_var_assign_obj;
 use crate::parser::abilities::param::gal_formal_param;
+use crate::util::duration::parse_duration;
 
 pub fn gal_vars(input: &mut &str) -> Result<GxlProps> {
     let mut vars = GxlProps::default();
@@ -49,7 +50,19 @@ pub fn act_param_define(input: &mut &str) -> Result<Vec<GxlFParam>> {
     Ok(args)
 }
 
-pub fn shell_opt_setting(key: String, value: String, expect: &mut ShellOption) {
+/// 参数值无法解析时(如 timeout) 解析失败
+pub fn shell_opt_setting(
+    name: String,
+    value: String,
+    expect: &mut ShellOption,
+    input: &mut &str,
+) -> Result<()> {
+    // 环境变量名区分大小写, 其余参数名不区分
+    if let Some(env) = name.strip_prefix("env.") {
+        expect.env.push((env.to_string(), value));
+        return Ok(());
+    }
+    let key = name.to_lowercase();
     if key == "suc" {
         expect.suc = Some(value);
     } else if key == "quiet" {
@@ -72,7 +85,20 @@ pub fn shell_opt_setting(key: String, value: String, expect: &mut ShellOption) {
         expect.log_lev = Some(parse_log((key.as_str(), value.as_str())));
     } else if key == "silence" && value.to_lowercase() == "true" {
         expect.secrecy = true;
+    } else if key == "timeout" {
+        match parse_duration(value.as_str()) {
+            Some(dur) => expect.timeout = Some(dur),
+            None => {
+                error!(target: "parse", "bad timeout value: {value}");
+                return fail.context(wn_desc("<timeout>")).parse_next(input);
+            }
+        }
+    } else if key == "dir" {
+        expect.dir = Some(value);
+    } else if key == "stdin" {
+        expect.stdin = Some(value);
     }
+    Ok(())
 }
 
 pub fn gal_prop(input: &mut &str) -> Result<GxlVar> {
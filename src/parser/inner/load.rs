@@ -2,6 +2,7 @@ use super::prelude::*;
 
 use crate::ability::{GxDownLoad, GxDownLoadBuilder};
 use crate::ability::{GxUpLoad, GxUpLoadBuilder};
+use crate::execution::retry::RetryPolicy;
 use crate::parser::domain::gal_keyword;
 pub fn gal_download(input: &mut &str) -> Result<GxDownLoad> {
     let mut down = GxDownLoadBuilder::default();
@@ -16,6 +17,13 @@ pub fn gal_download(input: &mut &str) -> Result<GxDownLoad> {
             down.username(v.clone());
         } else if k == "password" {
             down.password(v.clone());
+        } else if k == "retry" {
+            match RetryPolicy::parse(v) {
+                Some(policy) => {
+                    down.retry(policy);
+                }
+                None => return fail.context(wn_desc("<retry>")).parse_next(input),
+            }
         }
     }
     match down.build() {
@@ -42,6 +50,13 @@ pub fn gal_upload(input: &mut &str) -> Result<GxUpLoad> {
             down.username(v.clone());
         } else if k == "password" {
             down.password(v.clone());
+        } else if k == "retry" {
+            match RetryPolicy::parse(v) {
+                Some(policy) => {
+                    down.retry(policy);
+                }
+                None => return fail.context(wn_desc("<retry>")).parse_next(input),
+            }
         }
     }
     match down.build() {
@@ -86,4 +101,18 @@ mod tests {
         assert_eq!(data, "");
         assert_eq!(obj.svc_url(), "https://github/galaxy");
     }
+    #[test]
+    fn parse_gx_upload_retry() {
+        once_init_log();
+        let mut data = r#"
+             gx.upload (
+             url : "https://github/galaxy",
+             method : "put",
+             local_file : "gsys" ,
+             retry : "count=2,delay=3s",
+             ) ;"#;
+        let obj = gal_upload(&mut data).assert();
+        assert_eq!(data, "");
+        assert_eq!(obj.retry(), &RetryPolicy::parse("count=2,delay=3s"));
+    }
 }
@@ -71,7 +71,7 @@ pub fn gal_read_cmd(input: &mut &str) -> Result<GxRead> {
         } else if key == "cmd" {
             builder.cmd(one.1);
         } else {
-            shell_opt_setting(key, one.1, &mut sh_opt);
+            shell_opt_setting(one.0, one.1, &mut sh_opt, input)?;
         }
     }
     builder.expect(sh_opt);
@@ -22,7 +22,7 @@ pub fn gal_shell(input: &mut &str) -> Result<GxShell> {
         } else if key == "out_var" {
             shell.set_out_var(one.1.to_opt());
         } else {
-            shell_opt_setting(key, one.1, &mut expect);
+            shell_opt_setting(one.0, one.1, &mut expect, input)?;
         }
     }
     shell.set_expect(expect);
@@ -111,6 +111,16 @@ pub fn gal_ann(input: &mut &str) -> Result<AnnDto> {
     Ok(AnnDto { funs })
 }
 
+/// 连续的多个注解合并为一个, 如 `#[inputs(...)] #[outputs(...)]`
+pub fn gal_anns(input: &mut &str) -> Result<Option<AnnDto>> {
+    let anns: Vec<AnnDto> = repeat(0.., gal_ann).parse_next(input)?;
+    if anns.is_empty() {
+        return Ok(None);
+    }
+    let funs = anns.into_iter().flat_map(|x| x.funs).collect();
+    Ok(Some(AnnDto { funs }))
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -312,6 +322,24 @@ mod tests {
         assert_funs(&mut input, expected);
     }
 
+    #[test]
+    fn test_gal_anns() {
+        let mut input =
+            "#[inputs(\"src/**/*.rs\", \"Cargo.lock\")]\n#[outputs(\"target/gflow\")]\nflow";
+        let ann = gal_anns(&mut input).assert().unwrap();
+        let expected = [
+            FunDto::new(
+                "inputs",
+                [(FST_ARG_TAG, "src/**/*.rs"), (SEC_ARG_TAG, "Cargo.lock")].to_vec(),
+            ),
+            FunDto::new("outputs", [(FST_ARG_TAG, "target/gflow")].to_vec()),
+        ]
+        .to_vec();
+        assert_eq!(ann.funs, expected);
+        assert_eq!(input, "\nflow");
+        assert!(gal_anns(&mut input).assert().is_none());
+    }
+
     fn assert_funs(input: &mut &str, expected: Vec<FunDto>) {
         let output = gal_fun_vec(input).unwrap();
         assert_eq!(output, expected);
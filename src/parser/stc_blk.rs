@@ -3,14 +3,21 @@ use super::inner::cmd::gal_cmd_block;
 use super::inner::gxl::gal_run;
 use super::inner::shell::gal_shell;
 use super::prelude::*;
+use orion_parse::atom::take_var_name;
 use orion_parse::define::take_var_ref_name;
-use winnow::combinator::repeat;
+use orion_parse::symbol::{symbol_bracket_beg, symbol_bracket_end, symbol_comma};
+use winnow::ascii::multispace1;
+use winnow::combinator::{delimited, preceded, repeat};
 
 use crate::ability::prelude::GxlVar;
 use crate::components::gxl_block::{BlockAction, BlockNode};
-use crate::components::gxl_loop::GxlLoop;
-use crate::parser::cond::gal_cond;
+use crate::components::gxl_loop::{GxlLoop, WHILE_MAX_LOOP};
+use crate::components::gxl_try::GxlTry;
+use crate::execution::runnable::BlockCtrl;
+use crate::parser::abilities::define::gal_gxl_object;
+use crate::parser::cond::{gal_cond, gal_exp};
 use crate::parser::inner::archive::{gal_tar, gal_untar};
+use crate::primitive::GxlObject;
 
 use super::atom::spaced;
 use super::domain::{gal_block_beg, gal_block_end, gal_keyword};
@@ -54,6 +61,31 @@ pub fn gal_block_code(input: &mut &str) -> Result<BlockNode> {
     //Ok(block)
 }
 
+/// repl 输入: 赋值与语句可交替出现, 直到输入结束;
+/// block 中的赋值先于语句执行, 语句之后的赋值开始新的 block, 以保持输入顺序
+pub fn gal_repl_code(input: &mut &str) -> Result<Vec<BlockNode>> {
+    let mut blocks = Vec::new();
+    let mut block = BlockNode::new();
+    loop {
+        skip_spaces_block.parse_next(input)?;
+        if input.trim().is_empty() {
+            blocks.push(block);
+            return Ok(blocks);
+        }
+        if let Some(prop) = opt(gal_prop).parse_next(input)? {
+            if !block.items().is_empty() {
+                blocks.push(std::mem::take(&mut block));
+            }
+            block.append(prop);
+            continue;
+        }
+        let senten = gal_sentens_item
+            .context(wn_desc("<senten>"))
+            .parse_next(input)?;
+        block.append(senten);
+    }
+}
+
 pub fn gal_sentens_item(input: &mut &str) -> Result<BlockAction> {
     multispace0(input)?;
     if starts_with("if", input) {
@@ -62,6 +94,20 @@ pub fn gal_sentens_item(input: &mut &str) -> Result<BlockAction> {
     if starts_with("for", input) {
         return gal_loop.map(BlockAction::Loop).parse_next(input);
     }
+    if starts_with(("try", multispace0, "{"), input) {
+        return gal_try.map(BlockAction::Try).parse_next(input);
+    }
+    if starts_with("while", input) {
+        return gal_while.map(BlockAction::Loop).parse_next(input);
+    }
+    if starts_with(("return", alt((multispace1, ";"))), input) {
+        return gal_return.map(BlockAction::Return).parse_next(input);
+    }
+    if starts_with(("break", multispace0, ";"), input)
+        || starts_with(("continue", multispace0, ";"), input)
+    {
+        return gal_block_ctrl.map(BlockAction::Ctrl).parse_next(input);
+    }
     if starts_with("gx.cmd", input) {
         return gal_cmd.map(BlockAction::Command).parse_next(input);
     }
@@ -127,16 +173,143 @@ pub fn gal_loop(input: &mut &str) -> Result<GxlLoop> {
     skip_spaces_block(input)?;
     gal_keyword("for", input)?;
 
-    let (cur_name, _, val_name) = (
+    // for ${V} in ... 或 for ${K}, ${V} in ...
+    let first = spaced(take_var_ref_name)
+        .context(wn_desc("<cur-var>"))
+        .parse_next(input)?;
+    let second = opt(preceded(
+        symbol_comma,
         spaced(take_var_ref_name).context(wn_desc("<cur-var>")),
-        spaced("in").context(wn_desc("in")),
-        spaced(take_var_ref_name).context(wn_desc("<var-set>")),
-    )
+    ))
+    .parse_next(input)?;
+    let (key_name, cur_name) = match second {
+        Some(cur_name) => (Some(first), cur_name),
+        None => (None, first),
+    };
+    spaced("in").context(wn_desc("in")).parse_next(input)?;
+    let obj = if starts_with("range", input) {
+        if key_name.is_some() {
+            return fail
+                .context(wn_desc("<range-without-key>"))
+                .parse_next(input);
+        }
+        let (beg, end) = gal_range.parse_next(input)?;
+        GxlLoop::range(cur_name, beg, end, BlockNode::new())
+    } else {
+        let val_name = spaced(take_var_ref_name)
+            .context(wn_desc("<var-set>"))
+            .parse_next(input)?;
+        GxlLoop::new(cur_name, val_name, BlockNode::new()).with_key(key_name)
+    };
+    let parallel = opt(gal_loop_parallel).parse_next(input)?;
+    let block = gal_block.parse_next(input)?;
+    skip_spaces_block(input)?;
+    multispace0(input)?;
+    Ok(obj.with_body(block).with_parallel(parallel))
+}
+
+// parallel(N)
+fn gal_loop_parallel(input: &mut &str) -> Result<usize> {
+    gal_keyword("parallel", input)?;
+    symbol_bracket_beg.parse_next(input)?;
+    let limit = spaced(digit1.parse_to::<usize>())
+        .context(wn_desc("<parallel-limit>"))
+        .parse_next(input)?;
+    symbol_bracket_end.parse_next(input)?;
+    Ok(limit)
+}
+
+// range(beg, end)
+fn gal_range(input: &mut &str) -> Result<(GxlObject, GxlObject)> {
+    gal_keyword("range", input)?;
+    symbol_bracket_beg.parse_next(input)?;
+    let beg = spaced(gal_gxl_object)
+        .context(wn_desc("<range-beg>"))
         .parse_next(input)?;
+    symbol_comma.parse_next(input)?;
+    let end = spaced(gal_gxl_object)
+        .context(wn_desc("<range-end>"))
+        .parse_next(input)?;
+    symbol_bracket_end.parse_next(input)?;
+    Ok((beg, end))
+}
+
+/// while <cond> [max N] { ... }
+pub fn gal_while(input: &mut &str) -> Result<GxlLoop> {
+    skip_spaces_block(input)?;
+    gal_keyword("while", input)?;
+    let cond = gal_exp.context(wn_desc("<while-cond>")).parse_next(input)?;
+    let max = opt(preceded(
+        spaced("max"),
+        spaced(digit1.parse_to::<u32>()).context(wn_desc("<max-count>")),
+    ))
+    .parse_next(input)?;
     let block = gal_block.parse_next(input)?;
     skip_spaces_block(input)?;
     multispace0(input)?;
-    Ok(GxlLoop::new(cur_name, val_name, block))
+    Ok(GxlLoop::while_loop(
+        cond,
+        max.unwrap_or(WHILE_MAX_LOOP),
+        block,
+    ))
+}
+
+/// try { ... } [catch [(err)] { ... }] [finally { ... }]
+pub fn gal_try(input: &mut &str) -> Result<GxlTry> {
+    skip_spaces_block(input)?;
+    gal_keyword("try", input)?;
+    let mut obj = GxlTry::new(gal_block.parse_next(input)?);
+    skip_spaces_block(input)?;
+    if starts_with("catch", input) {
+        gal_keyword("catch", input)?;
+        let var = opt(delimited(
+            spaced(symbol_bracket_beg),
+            take_var_name.context(wn_desc("<err-var>")),
+            spaced(symbol_bracket_end),
+        ))
+        .parse_next(input)?;
+        let block = gal_block.parse_next(input)?;
+        obj = obj.with_catch(var.map(String::from), block);
+        skip_spaces_block(input)?;
+    }
+    if starts_with("finally", input) {
+        gal_keyword("finally", input)?;
+        obj = obj.with_finally(Some(gal_block.parse_next(input)?));
+        skip_spaces_block(input)?;
+    }
+    if obj.catch_block().is_none() && obj.finally_block().is_none() {
+        return fail
+            .context(wn_desc("<catch-or-finally>"))
+            .parse_next(input);
+    }
+    multispace0(input)?;
+    Ok(obj)
+}
+
+/// return; 或 return <value>;
+pub fn gal_return(input: &mut &str) -> Result<Option<GxlObject>> {
+    skip_spaces_block(input)?;
+    gal_keyword("return", input)?;
+    let value = opt(spaced(gal_gxl_object))
+        .context(wn_desc("<return-value>"))
+        .parse_next(input)?;
+    symbol_semicolon
+        .context(wn_desc("<return-end>"))
+        .parse_next(input)?;
+    multispace0(input)?;
+    Ok(value)
+}
+
+pub fn gal_block_ctrl(input: &mut &str) -> Result<BlockCtrl> {
+    multispace0(input)?;
+    let ctrl = alt((
+        "break".value(BlockCtrl::Break),
+        "continue".value(BlockCtrl::Continue),
+    ))
+    .parse_next(input)?;
+    symbol_semicolon.parse_next(input)?;
+    multispace0(input)?;
+    Ok(ctrl)
 }
 
 #[cfg(test)]
@@ -144,9 +317,16 @@ mod tests {
 
     use orion_error::TestAssert;
 
-    use crate::parser::{
-        inner::run_gxl,
-        stc_blk::{gal_block, gal_loop},
+    use crate::{
+        components::{
+            gxl_block::BlockAction,
+            gxl_loop::{LoopKind, WHILE_MAX_LOOP},
+        },
+        parser::{
+            inner::run_gxl,
+            stc_blk::{gal_block, gal_loop, gal_return, gal_try, gal_while},
+        },
+        primitive::GxlObject,
     };
 
     #[test]
@@ -188,6 +368,137 @@ mod tests {
         assert_eq!(data, "");
     }
     #[test]
+    fn test_for_key() {
+        let mut data = r#"
+            for  ${K}, ${V} in ${SERVICES} {
+                gx.echo ( value  : "${K}=${V}"  );
+             }
+        "#;
+        let obj = run_gxl(gal_loop, &mut data).assert();
+        assert_eq!(obj.key_name(), &Some("K".to_string()));
+        assert_eq!(obj.cur_name(), "V");
+        assert_eq!(data, "");
+
+        let mut data = r#"
+            for  ${I}, ${V} in range(0, 3) {
+                gx.echo ( value  : "${V}"  );
+             }
+        "#;
+        assert!(gal_loop(&mut data).is_err());
+    }
+    #[test]
+    fn test_for_parallel() {
+        let mut data = r#"
+            for  ${SVC} in ${SERVICES} parallel(4) {
+                gx.echo ( value  : "${SVC}"  );
+             }
+        "#;
+        let obj = run_gxl(gal_loop, &mut data).assert();
+        assert_eq!(obj.parallel(), &Some(4));
+        assert_eq!(data, "");
+    }
+    #[test]
+    fn test_for_range() {
+        let mut data = r#"
+            for  ${I} in range(1, ${COUNT}) {
+                gx.echo ( value  : "${I}"  );
+             }
+        "#;
+        let obj = run_gxl(gal_loop, &mut data).assert();
+        assert!(matches!(obj.kind(), LoopKind::Range(..)));
+        assert_eq!(data, "");
+    }
+    #[test]
+    fn test_while() {
+        let mut data = r#"
+            while ${STATUS} != "done" max 30 {
+                gx.cmd ( "sleep 1" );
+                if ${STATUS} == "fail" {
+                    break;
+                }
+                continue ;
+             }
+        "#;
+        let obj = run_gxl(gal_while, &mut data).assert();
+        assert!(matches!(obj.kind(), LoopKind::While(_, 30)));
+        assert_eq!(obj.body().items().len(), 3);
+        assert_eq!(data, "");
+
+        let mut data = r#"
+            while ${STATUS} != "done" {
+                gx.cmd ( "sleep 1" );
+             }
+        "#;
+        let obj = run_gxl(gal_while, &mut data).assert();
+        assert!(matches!(obj.kind(), LoopKind::While(_, WHILE_MAX_LOOP)));
+    }
+    #[test]
+    fn test_return() {
+        let mut data = r#"
+            {
+            if ${A} == "x" {
+                return;
+            }
+            return "${MAJOR}.${MINOR}";
+            }
+        "#;
+        let blk = run_gxl(gal_block, &mut data).assert();
+        assert_eq!(blk.items().len(), 2);
+        assert!(matches!(blk.items()[1], BlockAction::Return(Some(_))));
+        assert_eq!(data, "");
+
+        let mut data = "return ${VER} ;";
+        let value = run_gxl(gal_return, &mut data).assert();
+        assert_eq!(value, Some(GxlObject::from_ref("VER")));
+    }
+    #[test]
+    fn test_repl_code() {
+        let mut data = r#"A = "x"; gx.echo ( "${A}" ); if ${A} == "x" { gx.echo ( "yes" ); }"#;
+        let blks = run_gxl(gal_repl_code, &mut data).assert();
+        assert_eq!(blks.len(), 1);
+        assert_eq!(blks[0].props().len(), 1);
+        assert_eq!(blks[0].items().len(), 2);
+        // 语句之后的赋值进入新的 block
+        let mut data = r#"A = "x"; gx.echo ( "${A}" ); B = "${A}-y"; C = "z"; gx.echo ( "${B}" );"#;
+        let blks = run_gxl(gal_repl_code, &mut data).assert();
+        assert_eq!(blks.len(), 2);
+        assert_eq!(blks[0].props().len(), 1);
+        assert_eq!(blks[0].items().len(), 1);
+        assert_eq!(blks[1].props().len(), 2);
+        assert_eq!(blks[1].items().len(), 1);
+        let mut data = "gx.echo ( ";
+        assert!(gal_repl_code(&mut data).is_err());
+    }
+    #[test]
+    fn test_try() {
+        let mut data = r#"
+            {
+            try {
+                gx.cmd ( "make build" );
+            } catch (err) {
+                gx.echo ( value : "${ERR.REASON}: ${ERR.MSG}" );
+            } finally {
+                gx.cmd ( "rm -rf ./tmp" );
+            }
+            try {
+                gx.cmd ( "make build" );
+            } finally {
+                gx.cmd ( "rm -rf ./tmp" );
+            }
+            }
+        "#;
+        let blk = run_gxl(gal_block, &mut data).assert();
+        assert_eq!(blk.items().len(), 2);
+        assert_eq!(data, "");
+
+        let mut data = r#"
+            try {
+                gx.cmd ( "make build" );
+            }
+        "#;
+        assert!(gal_try(&mut data).is_err());
+    }
+    #[test]
     fn test_if_for() {
         let mut data = r#"
             {
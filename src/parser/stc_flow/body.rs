@@ -1,7 +1,9 @@
 use crate::components::gxl_flow::meta::FlowMeta;
 use crate::parser::prelude::*;
 
+use crate::calculate::ExpressEnum;
 use crate::components::GxlFlow;
+use crate::parser::cond::gal_exp;
 use crate::parser::stc_ann::gal_ann;
 use crate::parser::stc_blk::gal_block;
 
@@ -25,12 +27,37 @@ pub fn gal_stc_flow_body(input: &mut &str) -> Result<GxlFlow> {
 }
 pub fn gal_stc_flow(input: &mut &str) -> Result<GxlFlow> {
     skip_spaces_block(input)?;
+    let start = *input;
     let ann = opt(gal_ann).parse_next(input)?;
     let mut flow = gal_stc_flow_body.parse_next(input)?;
     flow.set_anns(ann);
+    flow.set_source(start[..start.len() - input.len()].to_string());
+    gal_flow_when(&mut flow, input)?;
     Ok(flow)
 }
 
+/// 注解设置后解析 #[when] 条件; mod 中的 flow 与单独解析的 flow 共用
+pub fn gal_flow_when(flow: &mut GxlFlow, input: &mut &str) -> Result<()> {
+    if let Some(text) = flow.meta().when_express() {
+        match gal_when_exp(text.as_str()) {
+            Ok(exp) => flow.set_when(Some(exp)),
+            Err(_) => return fail.context(wn_desc("<when-express>")).parse_next(input),
+        }
+    }
+    Ok(())
+}
+
+/// #[when("...")] 的条件表达式; 注解参数不能含双引号, 字符串用单引号书写
+fn gal_when_exp(text: &str) -> Result<ExpressEnum> {
+    let mut data = text;
+    let exp = gal_exp.parse_next(&mut data)?;
+    multispace0.parse_next(&mut data)?;
+    if !data.is_empty() {
+        return fail.parse_next(&mut data);
+    }
+    Ok(exp)
+}
+
 #[cfg(test)]
 mod tests {
     use orion_common::friendly::New3;
@@ -137,6 +164,34 @@ mod tests {
         );
     }
     #[test]
+    fn flow_when_test() {
+        let mut data = r#"
+    #[when("${GXL_OS_SYS} =* 'x86_64*' && ${MODE} == 'dev'")]
+    flow build {
+         gx.echo ( value  : "build"  );
+    };"#;
+        let flow = run_gxl(gal_stc_flow, &mut data).assert();
+        assert_eq!(data, "");
+        assert!(flow.when().is_some());
+
+        // 单引号字符串中的撇号
+        let mut data = r#"
+    #[when("${MSG} == 'it\'s done' || ${MODE} == 'dev'")]
+    flow build {
+         gx.echo ( value  : "build"  );
+    };"#;
+        let flow = run_gxl(gal_stc_flow, &mut data).assert();
+        assert_eq!(data, "");
+        assert!(flow.when().is_some());
+
+        let mut data = r#"
+    #[when("${MODE} ==")]
+    flow build {
+         gx.echo ( value  : "build"  );
+    };"#;
+        assert!(gal_stc_flow(&mut data).is_err());
+    }
+    #[test]
     fn flow_test6() {
         let mut data = r#"
         flow x {
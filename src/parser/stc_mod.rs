@@ -11,20 +11,24 @@ use crate::{
         GxlMod,
     },
     meta::GxlType,
-    parser::{gxl_fun::body::gal_stc_fun, stc_flow::body::gal_stc_flow_body},
+    parser::{
+        gxl_fun::body::gal_stc_fun,
+        stc_flow::body::{gal_flow_when, gal_stc_flow_body},
+    },
 };
 
 use super::{
     domain::{gal_block_beg, gal_block_end},
     inner::gal_prop,
     stc_act::gal_activity,
-    stc_ann::gal_ann,
+    stc_ann::{gal_ann, gal_anns},
     stc_base::gal_mod_head,
     stc_env::gal_stc_env_body,
 };
 pub fn gal_stc_mod_item(input: &mut &str) -> Result<ModItem> {
     skip_spaces_block.parse_next(input)?;
-    let ann = opt(gal_ann).parse_next(input)?;
+    let start = *input;
+    let ann = gal_anns.parse_next(input)?;
     skip_spaces_block.parse_next(input)?;
     if starts_with("env", input) {
         let mut env = gal_stc_env_body
@@ -38,6 +42,8 @@ pub fn gal_stc_mod_item(input: &mut &str) -> Result<ModItem> {
             .context(wn_desc("<flow>"))
             .parse_next(input)?;
         flow.set_anns(ann);
+        flow.set_source(start[..start.len() - input.len()].to_string());
+        gal_flow_when(&mut flow, input)?;
         return Ok(ModItem::Flow(flow));
     }
     if starts_with("fn", input) {
@@ -136,6 +142,29 @@ mod main : mod_a {
         assert_eq!(rgmod.props().items().len(), 2);
     }
 
+    #[test]
+    fn test_mod_item_when() {
+        let mut data = r#"
+  #[when("${MODE} == 'dev'")]
+  flow _dev {
+    gx.echo ( value : "dev" ) ;
+  }
+"#;
+        let item = run_gxl(gal_stc_mod_item, &mut data).assert();
+        let ModItem::Flow(flow) = item else {
+            panic!("need flow");
+        };
+        assert!(flow.when().is_some());
+        assert!(flow.source().starts_with("#[when("));
+        assert!(flow.source().contains(r#"gx.echo ( value : "dev" )"#));
+
+        let mut data = r#"
+  #[when("${MODE} ==")]
+  flow _dev ;
+"#;
+        assert!(run_gxl(gal_stc_mod_item, &mut data).is_err());
+    }
+
     #[test]
     fn test_mod_transaction() {
         let mut data = r#"
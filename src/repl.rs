@@ -0,0 +1,99 @@
+use std::io::{BufRead, Write};
+
+use orion_error::{ErrorConv, ErrorOwe};
+
+use crate::ability::prelude::TaskValue;
+use crate::components::gxl_spc::GxlSpace;
+use crate::context::ExecContext;
+use crate::err::{RunReason, RunResult};
+use crate::execution::runnable::AsyncRunnableWithSenderTrait;
+use crate::execution::VarSpace;
+use crate::parser::stc_blk::gal_repl_code;
+use crate::parser::stc_spc::WinnowErrorEx;
+use crate::traits::DependTrait;
+use crate::util::signal;
+
+const REPL_HELP: &str = "input gxl statements, eg: A = \"x\"; gx.echo ( \"${A}\" );\n:vars  show vars\n:quit  exit repl";
+
+/// gflow repl: 以 main mod 为上下文逐条执行 gxl 语句, 变量在多次输入之间保留
+pub struct GxlRepl {
+    spc: GxlSpace,
+    ctx: ExecContext,
+    vars: VarSpace,
+}
+
+impl GxlRepl {
+    /// 先执行所选 env 与 main mod 的属性
+    pub async fn new(
+        spc: GxlSpace,
+        envs: &[String],
+        vars: VarSpace,
+        out: Option<bool>,
+    ) -> RunResult<Self> {
+        let ctx = ExecContext::new(out, false).with_subcontext("repl");
+        let vars = spc.load_vars(&ctx, envs, vars).await?;
+        Ok(Self { spc, ctx, vars })
+    }
+
+    pub fn vars(&self) -> &VarSpace {
+        &self.vars
+    }
+
+    /// 解析并执行一段输入, 全部成功后保留其变量
+    pub async fn eval(&mut self, code: &str) -> RunResult<()> {
+        // 上一条输入被中断后, 后续输入仍可执行
+        signal::reset_interrupt();
+        let mut input = code;
+        let blocks = gal_repl_code(&mut input)
+            .map_err(WinnowErrorEx::from)
+            .owe(RunReason::Gxl("repl parse error".into()))?;
+        let mut vars = self.vars.clone();
+        for block in blocks {
+            let block = block.assemble("main", &self.spc).err_conv()?;
+            let TaskValue { vars: out, .. } = block
+                .async_exec(self.ctx.clone(), vars, None)
+                .await
+                .err_conv()?;
+            vars = out;
+        }
+        self.vars = vars;
+        Ok(())
+    }
+
+    /// 逐行读取输入, `{` 未闭合时继续读取下一行; 出错时打印错误并继续
+    pub async fn run<R: BufRead, W: Write>(&mut self, input: &mut R, out: &mut W) -> RunResult<()> {
+        let mut code = String::new();
+        loop {
+            let prompt = if code.is_empty() { "gxl> " } else { "...> " };
+            let _ = write!(out, "{prompt}");
+            let _ = out.flush();
+            let mut line = String::new();
+            if input.read_line(&mut line).owe_sys()? == 0 {
+                return Ok(());
+            }
+            if code.is_empty() {
+                match line.trim() {
+                    "" => continue,
+                    ":quit" | ":q" | "exit" => return Ok(()),
+                    ":vars" => {
+                        let _ = write!(out, "{}", self.vars.global());
+                        continue;
+                    }
+                    ":help" => {
+                        let _ = writeln!(out, "{REPL_HELP}");
+                        continue;
+                    }
+                    _ => {}
+                }
+            }
+            code.push_str(&line);
+            if code.matches('{').count() > code.matches('}').count() {
+                continue;
+            }
+            if let Err(e) = self.eval(&code).await {
+                let _ = writeln!(out, "{e}");
+            }
+            code.clear();
+        }
+    }
+}
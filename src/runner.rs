@@ -1,12 +1,22 @@
 use crate::{
-    err::{RunError, RunResult},
-    execution::VarSpace,
+    components::gxl_spc::ExecOptions,
+    err::{RunError, RunReason, RunResult},
+    execution::{
+        cache::CACHE_DIR,
+        lock::{FileLock, LockSpec},
+        matrix::MatrixCell,
+        state::{RunStore, KEEP_RUNS, STATE_DIR},
+        VarSpace,
+    },
     infra::DfxArgsGetter,
-    util::redirect::ReadSignal,
+    repl::GxlRepl,
+    util::{
+        duration::parse_duration, executor::SharedExecutor, redirect::ReadSignal, signal, step,
+    },
     GxLoader,
 };
 use clap::ArgAction;
-use orion_error::{ErrorConv, ErrorWith, StructError, UvsConfFrom};
+use orion_error::{ErrorConv, ErrorOwe, ErrorWith, StructError, UvsConfFrom};
 use std::{path::Path, sync::mpsc::Sender};
 
 pub struct GxlRunner {}
@@ -15,6 +25,19 @@ impl GxlRunner {
         cmd: GxlCmd,
         vars: VarSpace,
         sender: Option<Sender<ReadSignal>>,
+    ) -> RunResult<()> {
+        signal::reset_interrupt();
+        let executor =
+            SharedExecutor::from_args(cmd.record.as_deref(), cmd.replay.as_deref()).err_conv()?;
+        Self::run_with_executor(cmd, vars, sender, executor).await
+    }
+
+    /// 使用给定的命令执行器运行, 忽略 cmd 中的 --record / --replay; 供 gx.run 沿用上层的执行器
+    pub async fn run_with_executor(
+        cmd: GxlCmd,
+        vars: VarSpace,
+        sender: Option<Sender<ReadSignal>>,
+        executor: SharedExecutor,
     ) -> RunResult<()> {
         let loader = GxLoader::new();
         if let Some(conf) = cmd.conf {
@@ -28,6 +51,65 @@ impl GxlRunner {
                 .await?
                 .assemble()
                 .err_conv()?;
+            let conf_dir = Path::new(conf.as_str()).parent().unwrap_or(Path::new("."));
+            let state_root = conf_dir.join(STATE_DIR);
+            let cache_dir = conf_dir.join(CACHE_DIR);
+            // 子命令会遮蔽同名 flow, 存在同名 flow 时报错, 而不是悄悄执行子命令
+            if let Some(action) = &cmd.action {
+                if spc.has_flow(action.name()) {
+                    return Err(RunError::from_conf(format!(
+                        "flow '{0}' conflicts with the '{0}' subcommand, rename the flow",
+                        action.name()
+                    )));
+                }
+            }
+            match cmd.action {
+                Some(GxlAction::Rollback { run_id }) => {
+                    let _lock = project_lock(conf.as_str(), cmd.lock_timeout.as_deref()).await?;
+                    let store = RunStore::resume(state_root, Some(run_id.as_str())).err_conv()?;
+                    let envs: Vec<String> = cmd.env.split(',').map(String::from).collect();
+                    spc.rollback(&store, &envs, cmd.quiet, vars).await?;
+                    return Ok(());
+                }
+                Some(GxlAction::Graph { flow, format }) => {
+                    let graph = spc.graph(flow.as_deref()).err_conv()?;
+                    match format {
+                        GraphFormat::Dot => print!("{}", graph.to_dot()),
+                        GraphFormat::Mermaid => print!("{}", graph.to_mermaid()),
+                    }
+                    return Ok(());
+                }
+                Some(GxlAction::Repl) => {
+                    let envs: Vec<String> = cmd.env.split(',').map(String::from).collect();
+                    let mut repl = GxlRepl::new(spc, &envs, vars, cmd.quiet).await?;
+                    let mut input = std::io::BufReader::new(std::io::stdin());
+                    repl.run(&mut input, &mut std::io::stdout()).await?;
+                    return Ok(());
+                }
+                Some(GxlAction::Test { filter, junit }) => {
+                    let _lock = project_lock(conf.as_str(), cmd.lock_timeout.as_deref()).await?;
+                    let envs: Vec<String> = cmd.env.split(',').map(String::from).collect();
+                    let opts = ExecOptions::default()
+                        .with_out(cmd.quiet)
+                        .with_executor(executor)
+                        .with_cache_dir(cache_dir);
+                    let report = spc.run_tests(&envs, filter.as_deref(), opts, vars).await?;
+                    if let Some(path) = junit {
+                        std::fs::write(&path, report.to_junit())
+                            .owe_res()
+                            .with(path)?;
+                    }
+                    if !report.is_suc() {
+                        return Err(RunReason::Exec(format!(
+                            "tests failed: {}",
+                            report.failed().join(", ")
+                        ))
+                        .into());
+                    }
+                    return Ok(());
+                }
+                None => {}
+            }
             if cmd.flow.is_empty() {
                 spc.show().err_conv()?;
                 return Ok(());
@@ -39,8 +121,42 @@ impl GxlRunner {
                     cmd.flow.clone()
                     //cmd.flow.iter().collect()
                 };
-                spc.exec(envs, flws, cmd.quiet, cmd.dryrun, vars, sender)
-                    .await?;
+                let mut opts = ExecOptions::default()
+                    .with_out(cmd.quiet)
+                    .with_dryrun(cmd.dryrun)
+                    .with_plan(cmd.plan)
+                    .with_executor(executor)
+                    .with_cache_dir(cache_dir);
+                step::enable(cmd.step);
+                // 同一个 _gal 的多次运行互斥
+                let _lock = if cmd.dryrun || cmd.plan {
+                    None
+                } else {
+                    project_lock(conf.as_str(), cmd.lock_timeout.as_deref()).await?
+                };
+                if let Some(spec) = cmd.matrix.as_deref() {
+                    // 各单元的执行进度互不相同, 矩阵模式不保存 --resume 状态
+                    let cells = MatrixCell::build(&envs, spec).err_conv()?;
+                    spc.exec_matrix(cells, flws, opts, vars, cmd.matrix_parallel)
+                        .await?;
+                    println!("\ngod job!");
+                    return Ok(());
+                }
+                if !cmd.dryrun && !cmd.plan {
+                    let store = match cmd.resume.as_deref() {
+                        Some(run_id) => RunStore::resume(state_root, Some(run_id)).err_conv()?,
+                        None => {
+                            let store = RunStore::create(state_root);
+                            if let Err(e) = store.prune(KEEP_RUNS) {
+                                warn!(target: "state", "prune run state failed: {e}");
+                            }
+                            store
+                        }
+                    };
+                    println!("run id: {}", store.run_id());
+                    opts = opts.with_store(store);
+                }
+                spc.exec_opts(envs, flws, opts, vars, sender).await?;
                 println!("\ngod job!");
             }
             Ok(())
@@ -49,7 +165,25 @@ impl GxlRunner {
         }
     }
 }
-use clap::Parser;
+
+async fn project_lock(conf: &str, timeout: Option<&str>) -> RunResult<Option<FileLock>> {
+    let timeout = match timeout {
+        Some(value) => Some(
+            parse_duration(value)
+                .ok_or_else(|| RunError::from_conf(format!("bad lock timeout: {value}")))?,
+        ),
+        None => None,
+    };
+    let gal_dir = Path::new(conf)
+        .parent()
+        .filter(|x| !x.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    LockSpec::project(gal_dir, timeout)
+        .acquire()
+        .await
+        .err_conv()
+}
+use clap::{Parser, Subcommand, ValueEnum};
 
 #[derive(Parser, Debug)] // requires `derive` feature
 #[command(version, about, long_about = None)]
@@ -80,9 +214,88 @@ pub struct GxlCmd {
     #[arg(long = "dryrun", action = ArgAction::SetTrue, default_value = "false")]
     pub dryrun: bool,
 
+    /// pause before each flow item and command, read debug commands from stdin
+    #[arg(long = "step", action = ArgAction::SetTrue, default_value = "false")]
+    pub step: bool,
+
+    /// print resolved commands of the flows in order without running them
+    #[arg(long = "plan", action = ArgAction::SetTrue, default_value = "false")]
+    pub plan: bool,
+
+    /// resume a failed run, skip finished items ; eg: gflow build --resume [run-id], default is latest
+    #[arg(long = "resume", num_args = 0..=1, default_missing_value = "", value_name = "run-id")]
+    pub resume: Option<String>,
+
+    /// run flows once per env, or once per value of a matrix var ; eg: -e dev,release --matrix, --matrix ARCH=x86,arm
+    #[arg(long = "matrix", num_args = 0..=1, default_missing_value = "", value_name = "var=values")]
+    pub matrix: Option<String>,
+
+    /// run matrix cells in parallel
+    #[arg(long = "matrix-parallel", action = ArgAction::SetTrue, default_value = "false")]
+    pub matrix_parallel: bool,
+
+    /// run commands and save their outputs and exit codes to file ; eg: --record cmds.json
+    #[arg(long = "record", value_name = "file")]
+    pub record: Option<String>,
+
+    /// return recorded outputs instead of running commands ; eg: --replay cmds.json
+    #[arg(long = "replay", value_name = "file")]
+    pub replay: Option<String>,
+
+    /// max wait for the project lock held by another gflow run ; eg: --lock-timeout 10m, default is wait forever
+    #[arg(long = "lock-timeout", value_name = "duration")]
+    pub lock_timeout: Option<String>,
+
     ///update remote gxl mod
     #[arg(long = "mod_up", action = ArgAction::SetTrue, default_value = "false")]
     pub mod_update: bool,
+
+    #[command(subcommand)]
+    pub action: Option<GxlAction>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum GxlAction {
+    /// replay pending undo flows of a run ; eg: gflow rollback <run-id>
+    Rollback {
+        #[arg(value_name = "run-id")]
+        run_id: String,
+    },
+    /// print mods, flows and their links ; eg: gflow graph build --format mermaid
+    Graph {
+        #[arg(value_name = "flow")]
+        flow: Option<String>,
+        #[arg(long = "format", value_enum, default_value = "dot")]
+        format: GraphFormat,
+    },
+    /// run flows annotated #[test], each with isolated vars ; eg: gflow test math --junit report.xml
+    Test {
+        #[arg(value_name = "filter")]
+        filter: Option<String>,
+        /// write results as JUnit XML
+        #[arg(long = "junit", value_name = "file")]
+        junit: Option<String>,
+    },
+    /// evaluate gxl statements interactively with env and main mod loaded ; eg: gflow -e dev repl
+    Repl,
+}
+
+impl GxlAction {
+    /// 子命令名, 与同名 flow 冲突时用于报错
+    pub fn name(&self) -> &'static str {
+        match self {
+            GxlAction::Rollback { .. } => "rollback",
+            GxlAction::Graph { .. } => "graph",
+            GxlAction::Test { .. } => "test",
+            GxlAction::Repl => "repl",
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum GraphFormat {
+    Dot,
+    Mermaid,
 }
 impl DfxArgsGetter for GxlCmd {
     fn debug_level(&self) -> usize {
@@ -93,3 +306,17 @@ impl DfxArgsGetter for GxlCmd {
         self.log.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action_name() {
+        let cmd = GxlCmd::try_parse_from(["gflow", "test", "math"]).unwrap();
+        assert_eq!(cmd.action.map(|x| x.name()), Some("test"));
+        let cmd = GxlCmd::try_parse_from(["gflow", "build", "pack"]).unwrap();
+        assert!(cmd.action.is_none());
+        assert_eq!(cmd.flow, vec!["build", "pack"]);
+    }
+}
@@ -0,0 +1,40 @@
+use std::time::Duration;
+
+/// 解析时长: "500ms", "30s", "10m", "2h", "1d"; 纯数字按秒处理
+pub fn parse_duration(input: &str) -> Option<Duration> {
+    let text = input.trim();
+    let pos = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let (num, unit) = text.split_at(pos);
+    let num = num.parse::<u64>().ok()?;
+    let dur = match unit.trim() {
+        "ms" => Duration::from_millis(num),
+        "" | "s" => Duration::from_secs(num),
+        "m" => Duration::from_secs(num * 60),
+        "h" => Duration::from_secs(num * 3600),
+        "d" => Duration::from_secs(num * 86400),
+        _ => return None,
+    };
+    Some(dur)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("500ms", Some(Duration::from_millis(500)))]
+    #[case("30s", Some(Duration::from_secs(30)))]
+    #[case("30", Some(Duration::from_secs(30)))]
+    #[case("10m", Some(Duration::from_secs(600)))]
+    #[case(" 2h ", Some(Duration::from_secs(7200)))]
+    #[case("1d", Some(Duration::from_secs(86400)))]
+    #[case("10x", None)]
+    #[case("m", None)]
+    #[case("", None)]
+    fn test_parse_duration(#[case] input: &str, #[case] expected: Option<Duration>) {
+        assert_eq!(parse_duration(input), expected);
+    }
+}
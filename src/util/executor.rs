@@ -0,0 +1,315 @@
+use std::fmt::Debug;
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use colored::*;
+use orion_error::ToStructError;
+use serde::{Deserialize, Serialize};
+
+use crate::evaluator::EnvExpress;
+use crate::expect::{LogicScope, ShellOption};
+use crate::util::shell::{os_sh, os_sh_exit};
+use crate::var::VarDict;
+use crate::{ExecReason, ExecResult};
+
+/// 外部命令的执行方式, gx.cmd, gx.shell, gx.read_cmd 与 activity 经由 ExecContext 调用
+pub trait CommandExecutor: Debug + Send + Sync {
+    #[allow(clippy::result_large_err)]
+    fn run(
+        &self,
+        scope: LogicScope,
+        target: &str,
+        cmd: &str,
+        opt: &ShellOption,
+        exp: &EnvExpress,
+        env: &VarDict,
+    ) -> ExecResult<(Vec<u8>, Vec<u8>)>;
+}
+
+/// 直接运行命令
+#[derive(Debug, Clone, Default)]
+pub struct ShellExecutor;
+
+impl CommandExecutor for ShellExecutor {
+    fn run(
+        &self,
+        scope: LogicScope,
+        target: &str,
+        cmd: &str,
+        opt: &ShellOption,
+        exp: &EnvExpress,
+        env: &VarDict,
+    ) -> ExecResult<(Vec<u8>, Vec<u8>)> {
+        os_sh(scope, target, cmd, opt, exp, env)
+    }
+}
+
+/// 一次命令执行的结果, 以脱敏后的命令为键
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Getters)]
+pub struct CmdRecord {
+    cmd: String,
+    code: i32,
+    stdout: String,
+    stderr: String,
+}
+
+impl CmdRecord {
+    pub fn new<S: Into<String>>(cmd: S, code: i32, stdout: &[u8], stderr: &[u8]) -> Self {
+        Self {
+            cmd: cmd.into(),
+            code,
+            stdout: String::from_utf8_lossy(stdout).to_string(),
+            stderr: String::from_utf8_lossy(stderr).to_string(),
+        }
+    }
+}
+
+/// --record: 运行命令并把结果写入文件; 每条命令后保存, 中途失败也保留已有记录
+#[derive(Debug)]
+pub struct RecordExecutor {
+    path: PathBuf,
+    records: Mutex<Vec<CmdRecord>>,
+}
+
+impl RecordExecutor {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: path.into(),
+            records: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl CommandExecutor for RecordExecutor {
+    fn run(
+        &self,
+        scope: LogicScope,
+        target: &str,
+        cmd: &str,
+        opt: &ShellOption,
+        exp: &EnvExpress,
+        env: &VarDict,
+    ) -> ExecResult<(Vec<u8>, Vec<u8>)> {
+        let key = exp.sec_eval(cmd)?;
+        let mut exit = None;
+        let result = os_sh_exit(scope, target, cmd, opt, exp, env, &mut exit);
+        // 命令结束时记录真实的退出码与输出; 无法启动时记录错误; 超时与中断不可重放, 不记录
+        let record = match (exit, &result) {
+            (Some(x), _) => Some(CmdRecord::new(key, x.code, &x.stdout, &x.stderr)),
+            (None, Err(e)) => match e.reason() {
+                ExecReason::OsCmd(_, code, stderr) => {
+                    Some(CmdRecord::new(key, *code, b"", stderr.as_bytes()))
+                }
+                _ => None,
+            },
+            (None, Ok(_)) => None,
+        };
+        if let Some(record) = record {
+            let mut records = self
+                .records
+                .lock()
+                .map_err(|e| ExecReason::Bug(e.to_string()))?;
+            records.push(record);
+            save_records(&self.path, &records)?;
+        }
+        result
+    }
+}
+
+/// --replay: 按命令返回记录的输出与退出码, 同一命令按记录顺序依次使用
+#[derive(Debug)]
+pub struct ReplayExecutor {
+    path: PathBuf,
+    records: Mutex<Vec<(bool, CmdRecord)>>,
+}
+
+impl ReplayExecutor {
+    pub fn load<P: Into<PathBuf>>(path: P) -> ExecResult<Self> {
+        let path = path.into();
+        let content = fs::read_to_string(&path)
+            .map_err(|e| ExecReason::Io(format!("{}: {e}", path.display())))?;
+        let records: Vec<CmdRecord> = serde_json::from_str(&content)
+            .map_err(|e| ExecReason::Io(format!("{}: {e}", path.display())))?;
+        Ok(Self {
+            path,
+            records: Mutex::new(records.into_iter().map(|x| (false, x)).collect()),
+        })
+    }
+
+    fn take(&self, cmd: &str) -> ExecResult<CmdRecord> {
+        let mut records = self
+            .records
+            .lock()
+            .map_err(|e| ExecReason::Bug(e.to_string()))?;
+        match records.iter_mut().find(|(used, x)| !*used && x.cmd == cmd) {
+            Some((used, record)) => {
+                *used = true;
+                Ok(record.clone())
+            }
+            None => {
+                ExecReason::Miss(format!("{} no record: {cmd}", self.path.display())).err_result()
+            }
+        }
+    }
+}
+
+impl CommandExecutor for ReplayExecutor {
+    fn run(
+        &self,
+        scope: LogicScope,
+        target: &str,
+        cmd: &str,
+        opt: &ShellOption,
+        exp: &EnvExpress,
+        _env: &VarDict,
+    ) -> ExecResult<(Vec<u8>, Vec<u8>)> {
+        let sec_cmd = exp.sec_eval(cmd)?;
+        debug!(target: target, "replay cmd : {sec_cmd}");
+        let record = self.take(&sec_cmd)?;
+        if record.code != 0 && !opt.expect.contains(&record.code) {
+            let fail_msg = exp.eval(opt.err.clone().unwrap_or(sec_cmd).as_str())?;
+            return Err(ExecReason::OsCmd(fail_msg, record.code, record.stderr).into());
+        }
+        if !opt.quiet(scope) {
+            let prefix = opt.prefix.clone().unwrap_or(target.to_string());
+            for line in record.stdout.lines() {
+                println!("{} {line}", format!("[{prefix}]").cyan());
+            }
+        }
+        Ok((record.stdout.into_bytes(), record.stderr.into_bytes()))
+    }
+}
+
+fn save_records(path: &Path, records: &[CmdRecord]) -> ExecResult<()> {
+    if let Some(dir) = path.parent().filter(|x| !x.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|e| ExecReason::Io(e.to_string()))?;
+    }
+    let content =
+        serde_json::to_string_pretty(records).map_err(|e| ExecReason::Io(e.to_string()))?;
+    fs::write(path, content).map_err(|e| ExecReason::Io(e.to_string()))?;
+    Ok(())
+}
+
+/// ExecContext 中共享的命令执行器, 默认直接运行
+#[derive(Debug, Clone)]
+pub struct SharedExecutor(Arc<dyn CommandExecutor>);
+
+impl Default for SharedExecutor {
+    fn default() -> Self {
+        Self::new(ShellExecutor)
+    }
+}
+
+impl SharedExecutor {
+    pub fn new<E: CommandExecutor + 'static>(executor: E) -> Self {
+        Self(Arc::new(executor))
+    }
+    /// --record <file> / --replay <file>
+    pub fn from_args(record: Option<&str>, replay: Option<&str>) -> ExecResult<Self> {
+        match (record, replay) {
+            (Some(_), Some(_)) => {
+                ExecReason::Args("--record and --replay can't be used together".into()).err_result()
+            }
+            (Some(path), None) => Ok(Self::new(RecordExecutor::new(path))),
+            (None, Some(path)) => Ok(Self::new(ReplayExecutor::load(path)?)),
+            (None, None) => Ok(Self::default()),
+        }
+    }
+}
+
+impl Deref for SharedExecutor {
+    type Target = dyn CommandExecutor;
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn run(executor: &SharedExecutor, cmd: &str) -> ExecResult<(Vec<u8>, Vec<u8>)> {
+        run_expect(executor, cmd, vec![0])
+    }
+
+    fn run_expect(
+        executor: &SharedExecutor,
+        cmd: &str,
+        expect: Vec<i32>,
+    ) -> ExecResult<(Vec<u8>, Vec<u8>)> {
+        let dict = VarDict::global_new();
+        let exp = EnvExpress::from_env_mix(dict.clone());
+        let opt = ShellOption {
+            quiet: true,
+            expect,
+            ..Default::default()
+        };
+        executor.run(LogicScope::Outer, "gx.cmd", cmd, &opt, &exp, &dict)
+    }
+
+    #[test]
+    fn test_record_replay() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("cmds.json");
+        let path_str = path.to_str().unwrap();
+
+        let record = SharedExecutor::from_args(Some(path_str), None).unwrap();
+        assert_eq!(run(&record, "echo a; echo b 1>&2").unwrap().0, b"a\n");
+        assert!(run(&record, "echo first").is_ok());
+        assert!(run(&record, "exit 3").is_err());
+
+        // 回放时不再运行命令, 同一命令也能按顺序取出不同的记录
+        let mut records: Vec<CmdRecord> =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(records.len(), 3);
+        records[1].stdout = "second\n".into();
+        records.insert(1, CmdRecord::new("echo first", 0, b"first\n", b""));
+        fs::write(&path, serde_json::to_string(&records).unwrap()).unwrap();
+
+        let replay = SharedExecutor::from_args(None, Some(path_str)).unwrap();
+        let (stdout, stderr) = run(&replay, "echo a; echo b 1>&2").unwrap();
+        assert_eq!(
+            (stdout.as_slice(), stderr.as_slice()),
+            (&b"a\n"[..], &b"b\n"[..])
+        );
+        assert_eq!(run(&replay, "echo first").unwrap().0, b"first\n");
+        assert_eq!(run(&replay, "echo first").unwrap().0, b"second\n");
+        let err = run(&replay, "exit 3").unwrap_err();
+        assert!(matches!(err.reason(), ExecReason::OsCmd(_, 3, _)));
+        let err = run(&replay, "echo first").unwrap_err();
+        assert!(matches!(err.reason(), ExecReason::Miss(_)));
+
+        assert!(SharedExecutor::from_args(Some(path_str), Some(path_str)).is_err());
+    }
+
+    #[test]
+    fn test_record_exit() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("cmds.json");
+        let path_str = path.to_str().unwrap();
+
+        let record = SharedExecutor::from_args(Some(path_str), None).unwrap();
+        assert!(run(&record, "echo out; echo err 1>&2; exit 3").is_err());
+        assert!(run_expect(&record, "echo partial; exit 2", vec![0, 2]).is_ok());
+        let records: Vec<CmdRecord> =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        // 失败命令保留 stdout, expect 的非零退出码原样记录
+        assert_eq!(
+            records,
+            vec![
+                CmdRecord::new("echo out; echo err 1>&2; exit 3", 3, b"out\n", b"err\n"),
+                CmdRecord::new("echo partial; exit 2", 2, b"partial\n", b""),
+            ]
+        );
+
+        let replay = SharedExecutor::from_args(None, Some(path_str)).unwrap();
+        let err = run(&replay, "echo out; echo err 1>&2; exit 3").unwrap_err();
+        assert!(matches!(err.reason(), ExecReason::OsCmd(_, 3, _)));
+        let (stdout, _) = run_expect(&replay, "echo partial; exit 2", vec![0, 2]).unwrap();
+        assert_eq!(stdout, b"partial\n");
+    }
+}
@@ -1,9 +1,13 @@
+pub mod duration;
+pub mod executor;
 mod git;
 pub mod http_handle;
 pub(crate) mod macs;
 pub mod path;
 pub mod serialize_time_format;
 pub mod shell;
+pub mod signal;
+pub mod step;
 pub mod str_utils;
 pub mod task_report;
 pub mod traits;
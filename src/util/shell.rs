@@ -1,13 +1,40 @@
-use duct_sh;
 use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Read, Write};
+#[cfg(unix)]
+use std::os::unix::process::ExitStatusExt;
+use std::process::{Child, Command, ExitStatus, Output, Stdio};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 
 use crate::evaluator::{EnvExpress, VarParser};
 use crate::expect::LogicScope;
 use crate::expect::ShellOption;
+use crate::util::signal;
 use crate::var::VarDict;
 use crate::{ExecReason, ExecResult};
 
 use colored::*;
+use orion_error::ToStructError;
+
+/// 外部命令会阻塞所在线程, 放到阻塞线程池中执行, 并发的 flow 与循环项才能同时运行
+pub async fn run_blocking<T, F>(task: F) -> ExecResult<T>
+where
+    F: FnOnce() -> ExecResult<T> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(task)
+        .await
+        .map_err(|e| ExecReason::Bug(format!("blocking task failed: {e}")).to_err())?
+}
+
+/// 命令结束时的真实退出码与输出
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CmdExit {
+    pub code: i32,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
 #[allow(clippy::result_large_err)]
 pub fn os_sh(
     scope: LogicScope,
@@ -16,6 +43,20 @@ pub fn os_sh(
     opt: &ShellOption,
     exp: &EnvExpress,
     env: &VarDict,
+) -> ExecResult<(Vec<u8>, Vec<u8>)> {
+    os_sh_exit(scope, target, cmd, opt, exp, env, &mut None)
+}
+
+/// 同 os_sh; 命令正常结束时将退出码与输出写入 exit, 无论是否符合 expect, 供 --record 使用
+#[allow(clippy::result_large_err)]
+pub fn os_sh_exit(
+    scope: LogicScope,
+    target: &str,
+    cmd: &str,
+    opt: &ShellOption,
+    exp: &EnvExpress,
+    env: &VarDict,
+    exit: &mut Option<CmdExit>,
 ) -> ExecResult<(Vec<u8>, Vec<u8>)> {
     let sec_cmd = exp.sec_eval(cmd)?;
     //let ee = EnvExpress::from_env();
@@ -29,20 +70,62 @@ pub fn os_sh(
     let exe_cmd = exp.eval(cmd)?;
     let mut run_env = env.clone();
     run_env.merge_dict(VarDict::from(std::env::vars()));
-    let output = duct_sh::sh_dangerous(exe_cmd)
-        .unchecked()
-        .stdout_capture()
-        .stderr_capture()
-        .full_env(run_env.export_str_map())
-        //.full_env(run_env.export())
-        .run();
+    let mut command = shell_command(exe_cmd);
+    command
+        .env_clear()
+        .envs(run_env.export_str_map())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(dir) = &opt.dir {
+        command.current_dir(exp.eval(dir)?);
+    }
+    for (key, value) in &opt.env {
+        command.env(key, exp.eval(value)?);
+    }
+    let stdin = match &opt.stdin {
+        Some(data) => {
+            command.stdin(Stdio::piped());
+            Some(exp.eval(data)?)
+        }
+        None => None,
+    };
+    // 仅有超时限制时使用独立进程组, 便于超时后整组清理
+    let isolate = opt.timeout.is_some();
+    let prefix = if opt.quiet(scope) {
+        None
+    } else {
+        Some(opt.prefix.clone().unwrap_or(target.to_string()))
+    };
+    let output = signal::spawn_child(&mut command, isolate).and_then(|mut child| {
+        let pid = child.id();
+        feed_stdin(&mut child, stdin);
+        let output = wait_output(child, prefix, opt.timeout);
+        signal::unregister_child(pid);
+        output
+    });
     let fail_msg = opt.err.clone().unwrap_or(sec_cmd.clone());
     let fail_msg = exp.eval(fail_msg.as_str())?;
+    let killed = match &output {
+        Ok(out) => is_killed(out.as_ref()),
+        Err(e) => e.kind() == std::io::ErrorKind::Interrupted,
+    };
+    if signal::is_interrupted() && killed {
+        return Err(ExecReason::Interrupted(fail_msg).into());
+    }
     match output {
         Err(e) => Err(ExecReason::OsCmd(fail_msg, 254, e.to_string()).into()),
-        Ok(out) => {
+        Ok(None) => {
+            let limit = opt.timeout.map(|x| format!("{x:?}")).unwrap_or_default();
+            Err(ExecReason::Timeout(fail_msg, limit).into())
+        }
+        Ok(Some(out)) => {
             let mut is_ok = false;
             if let Some(code) = out.status.code() {
+                *exit = Some(CmdExit {
+                    code,
+                    stdout: out.stdout.clone(),
+                    stderr: out.stderr.clone(),
+                });
                 let err_desp = "err msg from utf8 failed";
                 let out_msg = String::from_utf8(out.stdout.clone())
                     .map_err(|_| ExecReason::OsCmd(sec_cmd.clone(), 253, err_desp.to_string()))?;
@@ -61,18 +144,16 @@ pub fn os_sh(
                     }
                 }
 
+                // 输出已实时打印, 这里只记录日志
                 let log_level = opt.log_lev.unwrap_or(log::Level::Debug);
                 if !opt.quiet(scope) {
                     if !out_msg.is_empty() {
-                        println!("{out_msg}");
                         log!(target: target, log_level, "out:\n{out_msg}", );
                     }
                     if !err_msg.is_empty() {
                         if is_ok {
-                            println!("{}", err_msg.yellow());
                             log!(target: target, log_level, "out:\n{err_msg}", );
                         } else {
-                            println!("{}", err_msg.clone().red());
                             log!(target: target, log_level, "err:\n{err_msg}", );
                         }
                     }
@@ -88,6 +169,122 @@ pub fn os_sh(
     }
 }
 
+/// 等待命令结束, 期间逐行转发 stdout/stderr; 超过 timeout 时杀掉整个进程组并返回 None
+fn wait_output(
+    mut child: Child,
+    prefix: Option<String>,
+    timeout: Option<Duration>,
+) -> std::io::Result<Option<Output>> {
+    let out_reader = stream_lines(child.stdout.take(), prefix.clone(), false);
+    let err_reader = stream_lines(child.stderr.take(), prefix, true);
+    let status = match timeout {
+        None => Some(child.wait()?),
+        Some(timeout) => wait_timeout(&mut child, timeout)?,
+    };
+    let stdout = out_reader.join().unwrap_or_default();
+    let stderr = err_reader.join().unwrap_or_default();
+    Ok(status.map(|status| Output {
+        status,
+        stdout,
+        stderr,
+    }))
+}
+
+fn wait_timeout(child: &mut Child, timeout: Duration) -> std::io::Result<Option<ExitStatus>> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if Instant::now() >= deadline {
+            kill_process_group(child.id());
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(None);
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+}
+
+/// 在独立线程写入标准输入后关闭管道, 避免与输出管道互相阻塞
+fn feed_stdin(child: &mut Child, data: Option<String>) {
+    if let (Some(mut pipe), Some(data)) = (child.stdin.take(), data) {
+        std::thread::spawn(move || {
+            let _ = pipe.write_all(data.as_bytes());
+        });
+    }
+}
+
+#[cfg(unix)]
+fn shell_command(cmd: String) -> Command {
+    let mut command = Command::new("/bin/sh");
+    command.arg("-c").arg(cmd);
+    command
+}
+
+#[cfg(not(unix))]
+fn shell_command(cmd: String) -> Command {
+    let mut command = Command::new("cmd");
+    command.arg("/C").arg(cmd);
+    command
+}
+
+// 被信号终止或因超时被杀掉
+#[cfg(unix)]
+fn is_killed(out: Option<&Output>) -> bool {
+    out.is_none_or(|x| x.status.signal().is_some())
+}
+
+// 非 unix 平台没有信号退出状态, 只能识别超时
+#[cfg(not(unix))]
+fn is_killed(out: Option<&Output>) -> bool {
+    out.is_none()
+}
+
+#[cfg(unix)]
+fn kill_process_group(pid: u32) {
+    // 子进程以自身 pid 作为进程组 id
+    unsafe {
+        libc::kill(-(pid as i32), libc::SIGKILL);
+    }
+}
+
+// 非 unix 平台没有进程组, 由调用方 kill 子进程本身
+#[cfg(not(unix))]
+fn kill_process_group(_pid: u32) {}
+
+/// 读取管道直到关闭, 返回完整内容; 有 prefix 时每读到一行立即打印, stderr 的行打印到 stderr
+fn stream_lines<R: Read + Send + 'static>(
+    pipe: Option<R>,
+    prefix: Option<String>,
+    is_err: bool,
+) -> JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut data = Vec::new();
+        let Some(pipe) = pipe else {
+            return data;
+        };
+        let mut reader = BufReader::new(pipe);
+        let mut line = Vec::new();
+        while let Ok(size) = reader.read_until(b'\n', &mut line) {
+            if size == 0 {
+                break;
+            }
+            if let Some(prefix) = &prefix {
+                let text = String::from_utf8_lossy(&line);
+                let text = text.trim_end_matches(['\r', '\n']);
+                if is_err {
+                    eprintln!("{} {}", format!("[{prefix}]").cyan(), text.yellow());
+                } else {
+                    println!("{} {text}", format!("[{prefix}]").cyan());
+                }
+            }
+            data.append(&mut line);
+        }
+        data
+    })
+}
+
 fn show_cmd(sec_cmd: &String) {
     let mut cmd_elements = VecDeque::from(sec_cmd.split(' ').collect::<Vec<&str>>());
     let first_element = cmd_elements.pop_front();
@@ -109,6 +306,7 @@ fn show_cmd(sec_cmd: &String) {
 mod tests {
     use orion_variate::vars::ValueType;
 
+    use crate::traits::Setter;
     use crate::var::VarDict;
 
     use super::*;
@@ -134,4 +332,50 @@ mod tests {
         let (stdout, _stderr) = os_sh(LogicScope::Outer, "gx.sh", &cmd, &opt, &exp, &dict).unwrap();
         assert_eq!(stdout, b"galaxy\n");
     }
+    #[test]
+    fn sh_timeout_test() {
+        let dict = VarDict::global_new();
+        let exp = EnvExpress::from_env_mix(dict.clone());
+        let opt = ShellOption {
+            quiet: true,
+            timeout: Some(Duration::from_millis(300)),
+            ..Default::default()
+        };
+        let begin = Instant::now();
+        let cmd = "sleep 5 | sleep 5".to_string();
+        let err = os_sh(LogicScope::Outer, "gx.sh", &cmd, &opt, &exp, &dict).unwrap_err();
+        assert!(matches!(err.reason(), ExecReason::Timeout(..)));
+        assert!(begin.elapsed() < Duration::from_secs(3));
+    }
+    #[test]
+    fn sh_stream_test() {
+        let dict = VarDict::global_new();
+        let exp = EnvExpress::from_env_mix(dict.clone());
+        let opt = ShellOption {
+            quiet: false,
+            prefix: Some("main/build".into()),
+            ..Default::default()
+        };
+        let cmd = "echo a; echo b 1>&2; echo c".to_string();
+        let (stdout, stderr) = os_sh(LogicScope::Outer, "gx.sh", &cmd, &opt, &exp, &dict).unwrap();
+        assert_eq!(stdout, b"a\nc\n");
+        assert_eq!(stderr, b"b\n");
+    }
+    #[test]
+    fn sh_dir_env_stdin_test() {
+        let mut dict = VarDict::global_new();
+        dict.set("DATA", "from-stdin");
+        dict.set("MODE", "release");
+        let exp = EnvExpress::from_env_mix(dict.clone());
+        let opt = ShellOption {
+            quiet: true,
+            dir: Some("./src".into()),
+            env: vec![("BUILD_MODE".into(), "${MODE}".into())],
+            stdin: Some("${DATA}".into()),
+            ..Default::default()
+        };
+        let cmd = "basename $(pwd); echo $BUILD_MODE; cat".to_string();
+        let (stdout, _) = os_sh(LogicScope::Outer, "gx.sh", &cmd, &opt, &exp, &dict).unwrap();
+        assert_eq!(stdout, b"src\nrelease\nfrom-stdin");
+    }
 }
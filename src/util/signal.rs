@@ -0,0 +1,200 @@
+use std::io;
+#[cfg(unix)]
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+#[cfg(unix)]
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::Notify;
+
+/// 被 SIGINT/SIGTERM 中断时的退出码
+pub const INTERRUPT_EXIT_CODE: i32 = 130;
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+// 正在运行的子进程: (pid, 是否使用独立进程组)
+static CHILDREN: Mutex<Vec<(u32, bool)>> = Mutex::new(Vec::new());
+
+lazy_static! {
+    static ref INTERRUPT_NOTIFY: Notify = Notify::new();
+}
+
+/// 安装 SIGINT/SIGTERM 处理: 第一次信号转发给正在运行的子进程并标记中断,
+/// 执行层据此停止后续 hold 并回滚; 再次收到信号时直接退出
+#[cfg(unix)]
+pub fn install_signal_handler() -> std::io::Result<()> {
+    let mut sig_int = signal(SignalKind::interrupt())?;
+    let mut sig_term = signal(SignalKind::terminate())?;
+    tokio::spawn(async move {
+        loop {
+            let sig = tokio::select! {
+                _ = sig_int.recv() => libc::SIGINT,
+                _ = sig_term.recv() => libc::SIGTERM,
+            };
+            if is_interrupted() {
+                std::process::exit(INTERRUPT_EXIT_CODE);
+            }
+            println!("\ninterrupted, stopping running commands ...");
+            interrupt_with(sig);
+        }
+    });
+    Ok(())
+}
+
+/// 非 unix 平台只处理 Ctrl-C; 控制台中的子进程同样会收到 Ctrl-C
+#[cfg(not(unix))]
+pub fn install_signal_handler() -> std::io::Result<()> {
+    tokio::spawn(async move {
+        while tokio::signal::ctrl_c().await.is_ok() {
+            if is_interrupted() {
+                std::process::exit(INTERRUPT_EXIT_CODE);
+            }
+            println!("\ninterrupted, stopping running commands ...");
+            interrupt_with(SIGINT);
+        }
+    });
+    Ok(())
+}
+
+pub fn is_interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// 清除中断标记; 每次运行或 repl 的每条输入开始前调用, 上一次的中断不影响本次执行
+pub fn reset_interrupt() {
+    INTERRUPTED.store(false, Ordering::SeqCst);
+}
+
+/// 标记中断并终止当前登记的子进程
+pub fn interrupt() {
+    interrupt_with(SIGTERM);
+}
+
+/// 标记中断并把信号转发给登记的子进程
+pub fn interrupt_with(sig: i32) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+    if let Ok(children) = CHILDREN.lock() {
+        for child in children.iter().filter(|x| need_forward(**x, sig)) {
+            send_signal(*child, sig);
+        }
+    }
+    INTERRUPT_NOTIFY.notify_waiters();
+}
+
+#[cfg(unix)]
+const SIGINT: i32 = libc::SIGINT;
+#[cfg(unix)]
+const SIGTERM: i32 = libc::SIGTERM;
+#[cfg(not(unix))]
+const SIGINT: i32 = 2;
+#[cfg(not(unix))]
+const SIGTERM: i32 = 15;
+
+// 终端的 SIGINT 已发给整个前台进程组, 前台子进程不再重复转发;
+// 独立进程组收不到终端信号, 总是转发
+fn need_forward((_, isolate): (u32, bool), sig: i32) -> bool {
+    isolate || sig != SIGINT
+}
+
+/// 等待中断发生
+pub async fn wait_interrupt() {
+    let notified = INTERRUPT_NOTIFY.notified();
+    if is_interrupted() {
+        return;
+    }
+    notified.await;
+}
+
+/// 启动并登记子进程; 与 interrupt 互斥, 已中断时不再启动.
+/// isolate 时使用独立进程组, 便于超时后整组清理;
+/// 否则留在前台进程组, 交互式命令可以正常读取终端
+pub fn spawn_child(command: &mut Command, isolate: bool) -> io::Result<Child> {
+    #[cfg(unix)]
+    if isolate {
+        command.process_group(0);
+    }
+    let mut children = CHILDREN
+        .lock()
+        .map_err(|e| io::Error::other(e.to_string()))?;
+    if is_interrupted() {
+        return Err(io::Error::new(io::ErrorKind::Interrupted, "interrupted"));
+    }
+    let child = command.spawn()?;
+    children.push((child.id(), isolate));
+    Ok(child)
+}
+
+pub fn unregister_child(pid: u32) {
+    if let Ok(mut children) = CHILDREN.lock() {
+        children.retain(|(x, _)| *x != pid);
+    }
+}
+
+// 独立进程组的子进程整组转发
+#[cfg(unix)]
+fn send_signal((pid, isolate): (u32, bool), sig: i32) {
+    let target = if isolate { -(pid as i32) } else { pid as i32 };
+    unsafe {
+        libc::kill(target, sig);
+    }
+}
+
+// 非 unix 平台没有进程组与信号, 子进程由超时或 Ctrl-C 自行结束
+#[cfg(not(unix))]
+fn send_signal(_child: (u32, bool), _sig: i32) {}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::os::unix::process::ExitStatusExt;
+
+    use super::*;
+
+    fn is_registered(pid: u32) -> bool {
+        CHILDREN.lock().unwrap().iter().any(|(x, _)| *x == pid)
+    }
+
+    fn pgid(pid: u32) -> i32 {
+        unsafe { libc::getpgid(pid as i32) }
+    }
+
+    #[test]
+    fn test_spawn_foreground_child() {
+        let mut child = spawn_child(Command::new("sleep").arg("5"), false).unwrap();
+        let pid = child.id();
+        assert!(is_registered(pid));
+        // 与 gflow 同属一个进程组
+        assert_eq!(pgid(pid), pgid(std::process::id()));
+
+        send_signal((pid, false), libc::SIGTERM);
+        assert_eq!(child.wait().unwrap().signal(), Some(libc::SIGTERM));
+        unregister_child(pid);
+        assert!(!is_registered(pid));
+    }
+
+    #[test]
+    fn test_spawn_isolated_child() {
+        let mut command = Command::new("/bin/sh");
+        command.arg("-c").arg("sleep 5 & wait");
+        let mut child = spawn_child(&mut command, true).unwrap();
+        let pid = child.id();
+        assert!(is_registered(pid));
+        assert_eq!(pgid(pid), pid as i32);
+
+        // 整组转发, 后台的 sleep 也一并结束
+        let begin = std::time::Instant::now();
+        send_signal((pid, true), libc::SIGTERM);
+        assert!(child.wait().unwrap().signal().is_some());
+        assert!(begin.elapsed() < std::time::Duration::from_secs(2));
+        unregister_child(pid);
+        assert!(!is_registered(pid));
+    }
+
+    #[test]
+    fn test_need_forward() {
+        // 前台子进程已从终端收到 SIGINT, 只转发给独立进程组
+        assert!(!need_forward((1, false), libc::SIGINT));
+        assert!(need_forward((1, true), libc::SIGINT));
+        assert!(need_forward((1, false), libc::SIGTERM));
+    }
+}
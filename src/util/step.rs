@@ -0,0 +1,134 @@
+use std::io::{BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use orion_error::ToStructError;
+
+use crate::execution::VarSpace;
+use crate::traits::Setter;
+use crate::{ExecReason, ExecResult};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+// 并行分支同时暂停时逐个交互
+static PAUSE_LOCK: Mutex<()> = Mutex::new(());
+
+const STEP_HELP: &str = "commands: [enter]/next, vars, get KEY, set KEY=V, skip, continue, abort";
+
+/// 暂停点的处理结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StepDecision {
+    Run,
+    Skip,
+}
+
+/// --step: 在每个 hold 与块内语句执行前暂停
+pub fn enable(on: bool) {
+    ENABLED.store(on, Ordering::SeqCst);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+/// 从标准输入读取调试命令, 直到决定执行、跳过或中止
+pub fn pause(path: &str, what: &str, vars: &mut VarSpace) -> ExecResult<StepDecision> {
+    let _guard = PAUSE_LOCK.lock();
+    if !is_enabled() {
+        return Ok(StepDecision::Run);
+    }
+    let stdin = std::io::stdin();
+    pause_with(&mut stdin.lock(), &mut std::io::stdout(), path, what, vars)
+}
+
+pub fn pause_with<R: BufRead, W: Write>(
+    input: &mut R,
+    out: &mut W,
+    path: &str,
+    what: &str,
+    vars: &mut VarSpace,
+) -> ExecResult<StepDecision> {
+    let _ = writeln!(out, "[step] {path}\n  {what}");
+    loop {
+        let _ = write!(out, "(step) ");
+        let _ = out.flush();
+        let mut line = String::new();
+        // 输入结束时不再暂停
+        if input.read_line(&mut line).unwrap_or(0) == 0 {
+            enable(false);
+            return Ok(StepDecision::Run);
+        }
+        let line = line.trim();
+        let (cmd, arg) = line.split_once(' ').unwrap_or((line, ""));
+        let arg = arg.trim();
+        match cmd {
+            "" | "n" | "next" => return Ok(StepDecision::Run),
+            "s" | "skip" => return Ok(StepDecision::Skip),
+            "c" | "continue" => {
+                enable(false);
+                return Ok(StepDecision::Run);
+            }
+            "q" | "abort" => {
+                return ExecReason::Interrupted(format!("step abort at {path}")).err_result();
+            }
+            "vars" => {
+                let _ = write!(out, "{}", vars.global());
+            }
+            "get" if !arg.is_empty() => match vars.get(arg) {
+                Some(value) => {
+                    let _ = writeln!(out, "{arg} = {value}");
+                }
+                None => {
+                    let _ = writeln!(out, "{arg} undefined");
+                }
+            },
+            "set" => match arg.split_once('=') {
+                Some((key, value)) if !key.trim().is_empty() => {
+                    vars.global_mut().set(key.trim(), value.trim());
+                }
+                _ => {
+                    let _ = writeln!(out, "usage: set KEY=V");
+                }
+            },
+            _ => {
+                let _ = writeln!(out, "{STEP_HELP}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sec::{SecFrom, SecValueType};
+
+    fn run(cmds: &str, vars: &mut VarSpace) -> (ExecResult<StepDecision>, String) {
+        let mut input = cmds.as_bytes();
+        let mut out = Vec::new();
+        let result = pause_with(&mut input, &mut out, "main.build", "gx.cmd: ls", vars);
+        (result, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_step_commands() {
+        let mut vars = VarSpace::default();
+        let (result, out) = run("set MODE = dev\nget MODE\nvars\nskip\n", &mut vars);
+        assert_eq!(result.unwrap(), StepDecision::Skip);
+        assert!(out.starts_with("[step] main.build\n  gx.cmd: ls\n"));
+        assert!(out.contains("MODE = dev"));
+        assert_eq!(
+            vars.get("MODE"),
+            Some(SecValueType::nor_from("dev".to_string()))
+        );
+
+        let (result, out) = run("get NONE\nhelp\n\n", &mut vars);
+        assert_eq!(result.unwrap(), StepDecision::Run);
+        assert!(out.contains("NONE undefined"));
+        assert!(out.contains(STEP_HELP));
+
+        let (result, _) = run("abort\n", &mut vars);
+        assert!(matches!(
+            result.unwrap_err().reason(),
+            ExecReason::Interrupted(_)
+        ));
+    }
+}
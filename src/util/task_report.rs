@@ -26,7 +26,13 @@ pub fn task_local_report(out: ExecOut) {
         }
     }
 
-    let file_name = format!("{dir_path}/task_{now}.yaml",);
+    // 同一秒内的多份报告(如中断时的部分报告与 rollback 报告)不互相覆盖
+    let mut file_name = format!("{dir_path}/task_{now}.yaml",);
+    let mut seq = 1;
+    while Path::new(&file_name).exists() {
+        file_name = format!("{dir_path}/task_{now}_{seq}.yaml",);
+        seq += 1;
+    }
 
     // 创建task结果存储文件
     match File::create(&file_name) {
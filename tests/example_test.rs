@@ -1,8 +1,15 @@
 //extern crate galaxy_flow;
 //#[cfg(feature = "res_depend_test")]
 mod tests {
+    use galaxy_flow::components::gxl_spc::{ExecOptions, GxlSpace};
     use galaxy_flow::err::report_gxl_error;
+    use galaxy_flow::execution::job::Job;
+    use galaxy_flow::execution::lock::LockSpec;
+    use galaxy_flow::execution::matrix::MatrixCell;
+    use galaxy_flow::execution::state::RunStore;
+    use galaxy_flow::execution::trans::UndoFailPolicy;
     use galaxy_flow::execution::VarSpace;
+    use galaxy_flow::util::executor::SharedExecutor;
     use galaxy_flow::util::path::WorkDirWithLock;
     use galaxy_flow::{err::RunResult, infra::once_init_log, GxLoader};
     use orion_error::TestAssert;
@@ -53,6 +60,28 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test(flavor = "current_thread")]
+    async fn example_cmd_opts() -> RunResult<()> {
+        let _dir = WorkDirWithLock::change("./examples/shell");
+        let vars = VarSpace::sys_init().assert();
+        let loader = GxLoader::new();
+        let spc = loader
+            .parse_file("./_gal/work.gxl", false, &vars)
+            .await?
+            .assemble()
+            .assert();
+        spc.exec(
+            vec!["default".into()],
+            vec!["cmd_opts".into()],
+            Some(false),
+            false,
+            VarSpace::default(),
+            None,
+        )
+        .await?;
+        Ok(())
+    }
+
     #[tokio::test(flavor = "current_thread")]
     async fn example_function() -> RunResult<()> {
         //once_init_log();
@@ -76,6 +105,27 @@ mod tests {
         Ok(())
     }
     #[tokio::test(flavor = "current_thread")]
+    async fn example_function_return() -> RunResult<()> {
+        let _dir = WorkDirWithLock::change("./examples/fun");
+        let vars = VarSpace::sys_init().assert();
+        let loader = GxLoader::new();
+        let spc = loader
+            .parse_file("./_gal/work.gxl", false, &vars)
+            .await?
+            .assemble()
+            .assert();
+        spc.exec(
+            vec!["default".into()],
+            vec!["ret".into()],
+            Some(false),
+            false,
+            VarSpace::default(),
+            None,
+        )
+        .await?;
+        Ok(())
+    }
+    #[tokio::test(flavor = "current_thread")]
     async fn example_assert() -> RunResult<()> {
         //once_init_log();
         let _dir = WorkDirWithLock::change("./examples/assert");
@@ -131,24 +181,31 @@ mod tests {
             .await?
             .assemble()
             .assert();
-        let result = spc
-            .exec(
-                vec!["default".into()],
-                vec!["trans1".into()],
-                Some(false),
-                false,
-                VarSpace::default(),
-                None,
-            )
-            .await;
-        match result {
-            Ok(_) => {
-                panic!("need fail!");
-            }
-            Err(e) => {
-                report_gxl_error(e);
+        // trans1 的 undo 全部执行; trans3 的 undo 失败后按 on_undo_fail="abort" 放弃剩余 undo
+        for (flow, undo_step1) in [("trans1", true), ("trans3", false)] {
+            let _ = std::fs::remove_file("./.undo_log");
+            let result = spc
+                .exec(
+                    vec!["default".into()],
+                    vec![flow.into()],
+                    Some(false),
+                    false,
+                    VarSpace::default(),
+                    None,
+                )
+                .await;
+            match result {
+                Ok(_) => {
+                    panic!("need fail!");
+                }
+                Err(e) => {
+                    report_gxl_error(e);
+                }
             }
+            let log = std::fs::read_to_string("./.undo_log").unwrap_or_default();
+            assert_eq!(log.contains("undo_step1"), undo_step1, "{flow}");
         }
+        let _ = std::fs::remove_file("./.undo_log");
         Ok(())
     }
 
@@ -188,4 +245,567 @@ mod tests {
         assert!(fail.is_err());
         Ok(())
     }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn example_parallel() -> RunResult<()> {
+        once_init_log();
+        let _dir = WorkDirWithLock::change("./examples/parallel");
+        let loader = GxLoader::new();
+        let vars = VarSpace::sys_init().assert();
+        let spc = loader
+            .parse_file("./_gal/work.gxl", false, &vars)
+            .await?
+            .assemble()
+            .assert();
+        // lint 与 test 开始与结束时写日志, 并发执行时两者都开始后才有结束
+        let _ = std::fs::remove_file("./.parallel_log");
+        spc.exec(
+            vec!["default".into()],
+            vec!["ci".into()],
+            Some(false),
+            false,
+            VarSpace::default(),
+            None,
+        )
+        .await?;
+        let log = std::fs::read_to_string("./.parallel_log").assert();
+        std::fs::remove_file("./.parallel_log").assert();
+        let lines: Vec<&str> = log.lines().collect();
+        assert_eq!(lines.len(), 4, "{log}");
+        assert!(lines[..2].iter().all(|x| x.ends_with("_begin")), "{log}");
+        assert!(lines[2..].iter().all(|x| x.ends_with("_end")), "{log}");
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn example_timeout() -> RunResult<()> {
+        once_init_log();
+        let _dir = WorkDirWithLock::change("./examples/timeout");
+        let loader = GxLoader::new();
+        let vars = VarSpace::sys_init().assert();
+        let spc = loader
+            .parse_file("./_gal/work.gxl", false, &vars)
+            .await?
+            .assemble()
+            .assert();
+        spc.exec(
+            vec!["default".into()],
+            vec!["quick".into()],
+            Some(false),
+            false,
+            VarSpace::default(),
+            None,
+        )
+        .await?;
+        for flow in ["slow_cmd", "slow_flow"] {
+            let fail = spc
+                .exec(
+                    vec!["default".into()],
+                    vec![flow.into()],
+                    Some(false),
+                    false,
+                    VarSpace::default(),
+                    None,
+                )
+                .await;
+            assert!(fail.is_err());
+        }
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn example_retry() -> RunResult<()> {
+        once_init_log();
+        let _dir = WorkDirWithLock::change("./examples/retry");
+        let loader = GxLoader::new();
+        let vars = VarSpace::sys_init().assert();
+        let spc = loader
+            .parse_file("./_gal/work.gxl", false, &vars)
+            .await?
+            .assemble()
+            .assert();
+        for flow in ["start", "clean"] {
+            spc.exec(
+                vec!["default".into()],
+                vec![flow.into()],
+                Some(false),
+                false,
+                VarSpace::default(),
+                None,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn example_when() -> RunResult<()> {
+        let _dir = WorkDirWithLock::change("./examples/when");
+        let loader = GxLoader::new();
+        let vars = VarSpace::sys_init().assert();
+        let spc = loader
+            .parse_file("./_gal/work.gxl", false, &vars)
+            .await?
+            .assemble()
+            .assert();
+        spc.exec(
+            vec!["default".into()],
+            vec!["start".into()],
+            Some(false),
+            false,
+            VarSpace::default(),
+            None,
+        )
+        .await?;
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn example_loop() -> RunResult<()> {
+        let _dir = WorkDirWithLock::change("./examples/loop");
+        let loader = GxLoader::new();
+        let vars = VarSpace::sys_init().assert();
+        let spc = loader
+            .parse_file("./_gal/work.gxl", false, &vars)
+            .await?
+            .assemble()
+            .assert();
+        spc.exec(
+            vec!["default".into()],
+            vec!["start".into()],
+            Some(false),
+            false,
+            VarSpace::default(),
+            None,
+        )
+        .await?;
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn example_try() -> RunResult<()> {
+        let _dir = WorkDirWithLock::change("./examples/try");
+        let loader = GxLoader::new();
+        let vars = VarSpace::sys_init().assert();
+        let spc = loader
+            .parse_file("./_gal/work.gxl", false, &vars)
+            .await?
+            .assemble()
+            .assert();
+        spc.exec(
+            vec!["default".into()],
+            vec!["start".into()],
+            Some(false),
+            false,
+            VarSpace::default(),
+            None,
+        )
+        .await?;
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn example_resume() -> RunResult<()> {
+        once_init_log();
+        let _dir = WorkDirWithLock::change("./examples/resume");
+        let loader = GxLoader::new();
+        let vars = VarSpace::sys_init().assert();
+        let spc = loader
+            .parse_file("./_gal/work.gxl", false, &vars)
+            .await?
+            .assemble()
+            .assert();
+        let run = |store: RunStore| {
+            spc.exec_opts(
+                vec!["default".into()],
+                vec!["build".into()],
+                ExecOptions::default()
+                    .with_out(Some(false))
+                    .with_store(store),
+                VarSpace::default(),
+                None,
+            )
+        };
+        spc.exec(
+            vec!["default".into()],
+            vec!["clean".into()],
+            Some(false),
+            false,
+            VarSpace::default(),
+            None,
+        )
+        .await?;
+        assert!(run(RunStore::create("./_gal/.state")).await.is_err());
+        std::fs::write("./.build_fixed", "").assert();
+        run(RunStore::resume("./_gal/.state", None).assert()).await?;
+        let count = std::fs::read_to_string("./.prepare_count").assert();
+        assert_eq!(count.lines().count(), 1);
+        spc.exec(
+            vec!["default".into()],
+            vec!["clean".into()],
+            Some(false),
+            false,
+            VarSpace::default(),
+            None,
+        )
+        .await?;
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn example_rollback() -> RunResult<()> {
+        once_init_log();
+        let _dir = WorkDirWithLock::change("./examples/transaction");
+        let loader = GxLoader::new();
+        let vars = VarSpace::sys_init().assert();
+        let spc = loader
+            .parse_file("./_gal/work.gxl", false, &vars)
+            .await?
+            .assemble()
+            .assert();
+        // 模拟事务执行中被中断: 日志中留有未执行的 undo
+        let store = RunStore::create("./_gal/.state");
+        let mut journal = store.undo_journal().assert();
+        journal
+            .push(
+                "main._undo_step1".into(),
+                &VarSpace::default(),
+                UndoFailPolicy::Continue,
+            )
+            .assert();
+        journal
+            .push(
+                "main._undo_step2".into(),
+                &VarSpace::default(),
+                UndoFailPolicy::Continue,
+            )
+            .assert();
+        let _ = std::fs::remove_file("./.undo_log");
+
+        let spc = &spc;
+        let rollback = |store: RunStore| async move {
+            let store = RunStore::resume("./_gal/.state", Some(store.run_id().as_str())).assert();
+            let result = spc
+                .rollback(
+                    &store,
+                    &["default".to_string()],
+                    Some(false),
+                    VarSpace::default(),
+                )
+                .await;
+            (result, store)
+        };
+        let (result, store) = rollback(store).await;
+        result?;
+        assert!(store.undo_journal().assert().entries().is_empty());
+        std::fs::remove_dir_all(store.run_path()).assert();
+        assert!(std::fs::read_to_string("./.undo_log")
+            .assert()
+            .contains("undo_step1"));
+        std::fs::remove_file("./.undo_log").assert();
+
+        // 日志中记录的 abort 策略: _undo_fail 失败后不再执行 _undo_step1
+        let store = RunStore::create("./_gal/.state");
+        let mut journal = store.undo_journal().assert();
+        journal
+            .push(
+                "main._undo_step1".into(),
+                &VarSpace::default(),
+                UndoFailPolicy::Abort,
+            )
+            .assert();
+        journal
+            .push(
+                "main._undo_fail".into(),
+                &VarSpace::default(),
+                UndoFailPolicy::Abort,
+            )
+            .assert();
+        // undo 失败时 rollback 返回错误, 未完成的 undo 保留在日志中
+        let (result, store) = rollback(store).await;
+        assert!(result.is_err());
+        assert!(!std::path::Path::new("./.undo_log").exists());
+        let journal = store.undo_journal().assert();
+        assert_eq!(journal.entries().len(), 2);
+        assert_eq!(journal.entries()[1].flow(), "main._undo_fail");
+        std::fs::remove_dir_all(store.run_path()).assert();
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn example_hooks() -> RunResult<()> {
+        let _dir = WorkDirWithLock::change("./examples/hooks");
+        let loader = GxLoader::new();
+        let vars = VarSpace::sys_init().assert();
+        let spc = loader
+            .parse_file("./_gal/work.gxl", false, &vars)
+            .await?
+            .assemble()
+            .assert();
+        let _ = std::fs::remove_file("./.hook_fail");
+        let _ = std::fs::remove_file("./.hook_status");
+        let run = |flows: &[&str]| {
+            spc.exec(
+                vec!["default".into()],
+                flows.iter().map(|x| x.to_string()).collect::<Vec<_>>(),
+                Some(false),
+                false,
+                VarSpace::default(),
+                None,
+            )
+        };
+        // 一次运行多个 flow 时 hook 只执行一次
+        run(&["ok", "ok"]).await?;
+        assert!(!std::path::Path::new("./.hook_fail").exists());
+        assert!(run(&["broken", "ok"]).await.is_err());
+        // hook 能读到失败前 flow 设置的变量
+        let fail = std::fs::read_to_string("./.hook_fail").assert();
+        assert_eq!(fail.trim(), "main._fail deploy");
+        let status = std::fs::read_to_string("./.hook_status").assert();
+        assert_eq!(status, "suc\nfail\n");
+        std::fs::remove_file("./.hook_fail").assert();
+        std::fs::remove_file("./.hook_status").assert();
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn example_incremental() -> RunResult<()> {
+        let _dir = WorkDirWithLock::change("./examples/incremental");
+        let loader = GxLoader::new();
+        let vars = VarSpace::sys_init().assert();
+        let spc = loader
+            .parse_file("./_gal/work.gxl", false, &vars)
+            .await?
+            .assemble()
+            .assert();
+        let _ = std::fs::remove_dir_all("./_gal/.cache");
+        let _ = std::fs::remove_dir_all("./out");
+        let _ = std::fs::remove_file("./.build_count");
+        let run_env = |env: &str| {
+            spc.exec(
+                vec![env.to_string()],
+                vec!["build".into()],
+                Some(false),
+                false,
+                VarSpace::default(),
+                None,
+            )
+        };
+        let run = || run_env("default");
+        let count = || {
+            std::fs::read_to_string("./.build_count")
+                .assert()
+                .lines()
+                .count()
+        };
+        run().await?;
+        assert_eq!(count(), 1);
+        // 输入输出未变化, 跳过
+        run().await?;
+        assert_eq!(count(), 1);
+        // 输出被删除后重新执行
+        std::fs::remove_dir_all("./out").assert();
+        run().await?;
+        assert_eq!(count(), 2);
+        assert_eq!(
+            std::fs::read_to_string("./out/all.txt").assert(),
+            "hello\nworld\n"
+        );
+        // env 变化后重新执行
+        run_env("release").await?;
+        assert_eq!(count(), 3);
+        run_env("release").await?;
+        assert_eq!(count(), 3);
+        std::fs::remove_dir_all("./_gal/.cache").assert();
+        std::fs::remove_dir_all("./out").assert();
+        std::fs::remove_file("./.build_count").assert();
+        Ok(())
+    }
+
+    // 按 env 与矩阵变量构建单元后执行 flow
+    async fn run_matrix(
+        spc: &GxlSpace,
+        envs: &[&str],
+        spec: &str,
+        flow: &str,
+        parallel: bool,
+    ) -> RunResult<Vec<Job>> {
+        let envs: Vec<String> = envs.iter().map(|x| x.to_string()).collect();
+        let cells = MatrixCell::build(&envs, spec).assert();
+        spc.exec_matrix(
+            cells,
+            vec![flow.into()],
+            ExecOptions::default().with_out(Some(false)),
+            VarSpace::default(),
+            parallel,
+        )
+        .await
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn example_matrix() -> RunResult<()> {
+        let _dir = WorkDirWithLock::change("./examples/matrix");
+        let loader = GxLoader::new();
+        let vars = VarSpace::sys_init().assert();
+        let spc = loader
+            .parse_file("./_gal/work.gxl", false, &vars)
+            .await?
+            .assemble()
+            .assert();
+        let _ = std::fs::remove_file("./.matrix_out");
+        let jobs = run_matrix(&spc, &["dev", "release"], "", "build", false).await?;
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(jobs[1].name(), "release");
+        let out = std::fs::read_to_string("./.matrix_out").assert();
+        assert_eq!(out, "dev\nrelease\n");
+        std::fs::remove_file("./.matrix_out").assert();
+
+        // 按变量取值并行执行
+        run_matrix(&spc, &["release"], "ARCH=x86,arm", "pack", true).await?;
+        let out = std::fs::read_to_string("./.matrix_out").assert();
+        let mut lines: Vec<&str> = out.lines().collect();
+        lines.sort();
+        assert_eq!(lines, vec!["release-arm", "release-x86"]);
+        std::fs::remove_file("./.matrix_out").assert();
+
+        // 失败单元不影响其他单元, 汇总后返回错误
+        let result = run_matrix(&spc, &["broken", "dev"], "", "build", false).await;
+        assert!(result.is_err());
+        let out = std::fs::read_to_string("./.matrix_out").assert();
+        assert_eq!(out, "dev\n");
+        std::fs::remove_file("./.matrix_out").assert();
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn example_plan() -> RunResult<()> {
+        let _dir = WorkDirWithLock::change("./examples/plan");
+        let loader = GxLoader::new();
+        let vars = VarSpace::sys_init().assert();
+        let spc = loader
+            .parse_file("./_gal/work.gxl", false, &vars)
+            .await?
+            .assemble()
+            .assert();
+        let opts = ExecOptions::default().with_out(Some(false)).with_plan(true);
+        spc.exec_opts(
+            vec!["default".into()],
+            vec!["pack".into()],
+            opts,
+            VarSpace::default(),
+            None,
+        )
+        .await?;
+        // 只打印命令, 不产生任何文件
+        assert!(!std::path::Path::new("./.plan_read").exists());
+        assert!(!std::path::Path::new("./.plan_out").exists());
+        assert!(!std::path::Path::new("./.plan_out.conf").exists());
+        assert!(!std::path::Path::new("./.plan_out.tar.gz").exists());
+        assert!(!std::path::Path::new("./_gal/.report").exists());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn example_lock() -> RunResult<()> {
+        let _dir = WorkDirWithLock::change("./examples/lock");
+        let loader = GxLoader::new();
+        let vars = VarSpace::sys_init().assert();
+        let spc = loader
+            .parse_file("./_gal/work.gxl", false, &vars)
+            .await?
+            .assemble()
+            .assert();
+        let _ = std::fs::remove_file("./.deploy_log");
+        let run = || {
+            spc.exec(
+                vec!["default".into()],
+                vec!["deploy".into()],
+                Some(false),
+                false,
+                VarSpace::default(),
+                None,
+            )
+        };
+        // 锁被其他运行持有时等待超时
+        let held = LockSpec::new("gflow-example-deploy-prod", None)
+            .acquire()
+            .await
+            .assert();
+        assert!(run().await.is_err());
+        assert!(!std::path::Path::new("./.deploy_log").exists());
+        drop(held);
+        run().await?;
+        assert_eq!(
+            std::fs::read_to_string("./.deploy_log").assert(),
+            "deploy prod\n"
+        );
+        std::fs::remove_file("./.deploy_log").assert();
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn example_replay() -> RunResult<()> {
+        let _dir = WorkDirWithLock::change("./examples/replay");
+        let loader = GxLoader::new();
+        let vars = VarSpace::sys_init().assert();
+        let spc = loader
+            .parse_file("./_gal/work.gxl", false, &vars)
+            .await?
+            .assemble()
+            .assert();
+        let run = |executor: SharedExecutor| {
+            spc.exec_opts(
+                vec!["default".into()],
+                vec!["deploy".into()],
+                ExecOptions::default()
+                    .with_out(Some(false))
+                    .with_executor(executor),
+                VarSpace::default(),
+                None,
+            )
+        };
+        // 回放记录的输出, 不真正执行 git 与 ssh
+        let replay = SharedExecutor::from_args(None, Some("./cmds.json")).assert();
+        run(replay.clone()).await?;
+        // 记录已用完
+        assert!(run(replay).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn example_test() -> RunResult<()> {
+        let _dir = WorkDirWithLock::change("./examples/test");
+        let loader = GxLoader::new();
+        let vars = VarSpace::sys_init().assert();
+        let spc = loader
+            .parse_file("./_gal/work.gxl", false, &vars)
+            .await?
+            .assemble()
+            .assert();
+        let envs = vec!["default".to_string()];
+        let opts = ExecOptions::default().with_out(Some(false));
+        let report = spc
+            .run_tests(&envs, None, opts.clone(), VarSpace::default())
+            .await?;
+        let names: Vec<&str> = report.cases().iter().map(|x| x.name()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "main.test_join_ver",
+                "main.test_join_ver_wrong",
+                "main.test_cmd"
+            ]
+        );
+        assert!(report.is_suc());
+        assert!(report.cases()[1].error().is_some());
+        assert!(report
+            .to_junit()
+            .contains(r#"<testsuite name="gflow" tests="3" failures="0""#));
+
+        let report = spc
+            .run_tests(&envs, Some("join"), opts, VarSpace::default())
+            .await?;
+        assert_eq!(report.cases().len(), 2);
+        Ok(())
+    }
 }
@@ -0,0 +1,35 @@
+extern crate galaxy_flow;
+use galaxy_flow::execution::VarSpace;
+use galaxy_flow::infra::once_init_log;
+use galaxy_flow::GxLoader;
+use orion_error::TestAssert;
+
+#[tokio::test]
+async fn graph_flow() {
+    once_init_log();
+    let loader = GxLoader::new();
+
+    let vars = VarSpace::sys_init().assert();
+    let spc = loader
+        .parse_file("./tests/material/graph.gxl", false, &vars)
+        .await
+        .unwrap()
+        .assemble()
+        .assert();
+
+    let dot = spc.graph(None).assert().to_dot();
+    assert!(dot.contains("\"main.prepare\" -> \"main.build\" [label=\"pre\"];"));
+    assert!(dot.contains("\"main.build\" -> \"main.test\" [label=\"post\"];"));
+    assert!(dot.contains("\"main.build\" -> \"main._undo_build\" [label=\"undo\", style=dashed];"));
+    assert!(dot.contains("\"main.build\" -> \"main._dry_build\" [label=\"dryrun\", style=dashed];"));
+    assert!(dot.contains("\"main\" -> \"main.__into\" [label=\"entry\", style=dashed];"));
+    assert!(dot.contains("\"main\" -> \"main._notify\" [label=\"on_failure\", style=dashed];"));
+    assert!(dot.contains("\"main.other\""));
+
+    let mermaid = spc.graph(Some("build")).assert().to_mermaid();
+    assert!(mermaid.contains("main_prepare -->|pre| main_build"));
+    assert!(mermaid.contains("main -.->|entry| main___into"));
+    assert!(mermaid.contains("main_build -.->|undo| main__undo_build"));
+    assert!(!mermaid.contains("main_other"));
+    assert!(spc.graph(Some("none")).is_err());
+}
@@ -0,0 +1,59 @@
+extern crate galaxy_flow;
+use galaxy_flow::execution::VarSpace;
+use galaxy_flow::infra::once_init_log;
+use galaxy_flow::repl::GxlRepl;
+use galaxy_flow::sec::{SecFrom, SecValueType};
+use galaxy_flow::GxLoader;
+use orion_error::TestAssert;
+
+fn str_value(value: &str) -> Option<SecValueType> {
+    Some(SecValueType::nor_from(value.to_string()))
+}
+
+#[tokio::test]
+async fn repl_eval() {
+    once_init_log();
+    let loader = GxLoader::new();
+    let vars = VarSpace::sys_init().assert();
+    let spc = loader
+        .parse_file("./tests/material/repl.gxl", false, &vars)
+        .await
+        .unwrap()
+        .assemble()
+        .assert();
+
+    let mut repl = GxlRepl::new(spc, &["release".into()], vars, Some(false))
+        .await
+        .assert();
+    assert_eq!(repl.vars().get("ENV_MODE"), str_value("release"));
+
+    repl.eval(r#"VER = sys.join_ver( major : "1", minor : "2" );"#)
+        .await
+        .assert();
+    assert_eq!(repl.vars().get("VER"), str_value("1.2"));
+    repl.eval(r#"TAG = "${ENV_MODE}-${MAIN_NAME}-${VER}"; gx.echo ( "${TAG}" );"#)
+        .await
+        .assert();
+    assert_eq!(repl.vars().get("TAG"), str_value("release-repl-1.2"));
+    assert!(repl.eval("gx.echo ( ").await.is_err());
+    // 赋值与语句交替出现时按输入顺序执行
+    repl.eval(
+        r#"STEP = "1"; gx.assert ( value : "${STEP}" , expect : "1" ); STEP = "2"; gx.assert ( value : "${STEP}" , expect : "2" );"#,
+    )
+    .await
+    .assert();
+    assert_eq!(repl.vars().get("STEP"), str_value("2"));
+
+    // 未闭合的块继续读取, 出错后不影响后续输入
+    let mut input = "if ${ENV_MODE} == \"release\" {\n  gx.assert ( value : \"${VER}\" , expect : \"1.2\" );\n}\nbad input\nLAST = \"ok\";\n:vars\n:quit\nNEVER = \"x\";\n".as_bytes();
+    let mut out = Vec::new();
+    repl.run(&mut input, &mut out).await.assert();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("...> "));
+    assert!(out.contains("gxl error"));
+    assert!(out
+        .lines()
+        .any(|x| x.to_uppercase().starts_with("LAST") && x.ends_with(": ok")));
+    assert_eq!(repl.vars().get("LAST"), str_value("ok"));
+    assert_eq!(repl.vars().get("NEVER"), None);
+}